@@ -1,79 +1,207 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::RangeInclusive};
 
-use adventofcode::solve_day;
-use anyhow::anyhow;
+use adventofcode::{
+    parse::{self, ParseError},
+    report::Table,
+    solve_or_diagnose_day,
+};
 
 fn main() -> anyhow::Result<()> {
-    solve_day(file!(), part1, part2)
+    solve_or_diagnose_day(file!(), part1, part2, diagnose)
 }
 
-fn part1(input: &str) -> anyhow::Result<u64> {
-    let mut group1_ids = Vec::with_capacity(1_000);
-    let mut group2_ids = Vec::with_capacity(1_000);
-
-    for line in input.lines() {
-        let mut location_ids = line.split_whitespace();
-
-        let group1_id: u64 = location_ids
-            .next()
-            .ok_or(anyhow!("Line was missing group 1 location ID."))?
-            .parse()?;
-        let group2_id: u64 = location_ids
-            .next()
-            .ok_or(anyhow!("Line was missing group 2 location ID."))?
-            .parse()?;
-
-        group1_ids.push(group1_id);
-        group2_ids.push(group2_id);
+struct LocationLists {
+    group1_ids: Vec<u64>,
+    group2_ids: Vec<u64>,
+}
+
+impl LocationLists {
+    /// The widest span of IDs for which we count occurrences in a dense array rather than sorting
+    /// or hashing. Lists with a wider span than this still use counting if the span is small
+    /// relative to the number of IDs, since the counting pass is then still linear.
+    const DENSE_RANGE: u64 = 1 << 20;
+
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        let line_count = input.bytes().filter(|&byte| byte == b'\n').count() + 1;
+
+        let mut group1_ids = Vec::with_capacity(line_count);
+        let mut group2_ids = Vec::with_capacity(line_count);
+
+        for line in parse::lines(input) {
+            let [group1_id, group2_id]: [u64; 2] = line.extract()?;
+
+            group1_ids.push(group1_id);
+            group2_ids.push(group2_id);
+        }
+
+        Ok(Self {
+            group1_ids,
+            group2_ids,
+        })
+    }
+
+    /// The range spanned by every ID in both lists, if it's narrow enough to count occurrences of
+    /// each ID in a dense array.
+    fn dense_id_range(&self) -> Option<RangeInclusive<u64>> {
+        let all_ids = || self.group1_ids.iter().chain(&self.group2_ids);
+
+        let min_id = *all_ids().min()?;
+        let max_id = *all_ids().max()?;
+
+        let id_count = (self.group1_ids.len() + self.group2_ids.len()) as u64;
+
+        (max_id - min_id < Self::DENSE_RANGE.max(4 * id_count)).then_some(min_id..=max_id)
+    }
+
+    /// Counts the occurrences of each ID in `ids`, indexed by offset from the start of `id_range`.
+    fn dense_counts(ids: &[u64], id_range: &RangeInclusive<u64>) -> Vec<u64> {
+        let mut counts = vec![0; (id_range.end() - id_range.start() + 1) as usize];
+
+        for &id in ids {
+            counts[(id - id_range.start()) as usize] += 1;
+        }
+
+        counts
+    }
+
+    /// The sum of the distances between the smallest ID in each list, then the second smallest,
+    /// and so on.
+    fn total_distance(mut self) -> u64 {
+        let Some(id_range) = self.dense_id_range() else {
+            self.group1_ids.sort_unstable();
+            self.group2_ids.sort_unstable();
+
+            return self
+                .group1_ids
+                .into_iter()
+                .zip(self.group2_ids)
+                .map(|(id1, id2)| id1.abs_diff(id2))
+                .sum();
+        };
+
+        // Rather than sorting, walk both lists' counts in ascending ID order, pairing off as many
+        // occurrences of the current IDs as possible at each step.
+        let mut group1_counts = Self::dense_counts(&self.group1_ids, &id_range);
+        let mut group2_counts = Self::dense_counts(&self.group2_ids, &id_range);
+
+        let mut group1_offset = 0;
+        let mut group2_offset = 0;
+
+        let mut distance_sum = 0;
+
+        loop {
+            while group1_counts.get(group1_offset) == Some(&0) {
+                group1_offset += 1;
+            }
+            while group2_counts.get(group2_offset) == Some(&0) {
+                group2_offset += 1;
+            }
+
+            if group1_offset == group1_counts.len() || group2_offset == group2_counts.len() {
+                break distance_sum;
+            }
+
+            let pair_count = group1_counts[group1_offset].min(group2_counts[group2_offset]);
+
+            distance_sum += pair_count * group1_offset.abs_diff(group2_offset) as u64;
+
+            group1_counts[group1_offset] -= pair_count;
+            group2_counts[group2_offset] -= pair_count;
+        }
+    }
+
+    /// The number of times each ID appears in the second list.
+    fn group2_counts(&self) -> HashMap<u64, u64> {
+        let mut group2_counts: HashMap<u64, u64> = HashMap::with_capacity(self.group2_ids.len());
+
+        for &location_id in &self.group2_ids {
+            *group2_counts.entry(location_id).or_default() += 1;
+        }
+
+        group2_counts
     }
 
-    group1_ids.sort_unstable();
-    group2_ids.sort_unstable();
+    /// The sum of each ID in the first list, multiplied by the number of times it appears in the
+    /// second list.
+    fn similarity_score(&self) -> u64 {
+        if let Some(id_range) = self.dense_id_range() {
+            let group2_counts = Self::dense_counts(&self.group2_ids, &id_range);
+
+            return self
+                .group1_ids
+                .iter()
+                .map(|&id| id * group2_counts[(id - id_range.start()) as usize])
+                .sum();
+        }
+
+        let group2_counts = self.group2_counts();
+
+        self.group1_ids
+            .iter()
+            .map(|location_id| {
+                location_id * group2_counts.get(location_id).copied().unwrap_or_default()
+            })
+            .sum()
+    }
+}
 
-    let distance_sum = group1_ids
-        .into_iter()
-        .zip(group2_ids)
-        .map(|(id1, id2)| id1.abs_diff(id2))
-        .sum();
+fn part1(input: &str) -> anyhow::Result<u64> {
+    let location_lists = LocationLists::parse(input)?;
 
-    Ok(distance_sum)
+    Ok(location_lists.total_distance())
 }
 
 fn part2(input: &str) -> anyhow::Result<u64> {
-    let mut group1_ids = Vec::with_capacity(1_000);
-    let mut group2_ids = Vec::with_capacity(1_000);
-
-    for line in input.lines() {
-        let mut location_ids = line.split_whitespace();
-
-        let group1_id: u64 = location_ids
-            .next()
-            .ok_or(anyhow!("Line was missing group 1 location ID."))?
-            .parse()?;
-        let group2_id: u64 = location_ids
-            .next()
-            .ok_or(anyhow!("Line was missing group 2 location ID."))?
-            .parse()?;
-
-        group1_ids.push(group1_id);
-        group2_ids.push(group2_id);
+    let location_lists = LocationLists::parse(input)?;
+
+    Ok(location_lists.similarity_score())
+}
+
+/// Shows how each pair of IDs contributes to the total distance, and how each ID in the first list
+/// contributes to the similarity score.
+fn diagnose(input: &str) -> anyhow::Result<Vec<Table>> {
+    let mut location_lists = LocationLists::parse(input)?;
+
+    let group2_counts = location_lists.group2_counts();
+
+    let mut similarity_table = Table::new(
+        "Part 2: similarity score",
+        ["Line", "Left ID", "Right count", "Contribution"],
+    );
+    let mut similarity_score = 0;
+
+    for (index, &location_id) in location_lists.group1_ids.iter().enumerate() {
+        let count = group2_counts.get(&location_id).copied().unwrap_or_default();
+        let contribution = location_id * count;
+
+        similarity_score += contribution;
+        similarity_table.push_row([index as u64 + 1, location_id, count, contribution]);
     }
 
-    let mut group2_id_sums: HashMap<u64, u64> = HashMap::new();
+    similarity_table.set_footer(["Total", "", "", &similarity_score.to_string()]);
 
-    for location_id in group2_ids {
-        *group2_id_sums.entry(location_id).or_default() += location_id;
+    location_lists.group1_ids.sort_unstable();
+    location_lists.group2_ids.sort_unstable();
+
+    let mut distance_table = Table::new(
+        "Part 1: sorted pairing",
+        ["Rank", "Left ID", "Right ID", "Distance"],
+    );
+    let mut distance_sum = 0;
+
+    for (index, (&id1, &id2)) in location_lists
+        .group1_ids
+        .iter()
+        .zip(&location_lists.group2_ids)
+        .enumerate()
+    {
+        let distance = id1.abs_diff(id2);
+
+        distance_sum += distance;
+        distance_table.push_row([index as u64 + 1, id1, id2, distance]);
     }
 
-    let similarity_score = group1_ids
-        .into_iter()
-        .map(|location_id| {
-            group2_id_sums
-                .get(&location_id)
-                .copied()
-                .unwrap_or_default()
-        })
-        .sum();
+    distance_table.set_footer(["Total", "", "", &distance_sum.to_string()]);
 
-    Ok(similarity_score)
+    Ok(vec![distance_table, similarity_table])
 }
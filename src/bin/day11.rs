@@ -1,6 +1,7 @@
 use std::{collections::HashMap, str::FromStr};
 
-use adventofcode::solve_day;
+use adventofcode::{parse, solve_day};
+use anyhow::anyhow;
 
 fn main() -> anyhow::Result<()> {
     solve_day(file!(), part1, part2)
@@ -18,9 +19,16 @@ impl FromStr for Stone {
     }
 }
 
+fn parse_stones(input: &str) -> anyhow::Result<Vec<Stone>> {
+    let line = parse::lines(input)
+        .next()
+        .ok_or(anyhow!("Input has no stones."))?;
+
+    Ok(line.parse_split(' ')?)
+}
+
 fn part1(input: &str) -> anyhow::Result<u64> {
-    let stones: anyhow::Result<Vec<Stone>> = input.split(' ').map(str::parse::<Stone>).collect();
-    let mut stones = stones?;
+    let mut stones: Vec<Stone> = parse_stones(input)?;
 
     const BLINKS: u32 = 25;
 
@@ -60,15 +68,10 @@ fn part1(input: &str) -> anyhow::Result<u64> {
 }
 
 fn part2(input: &str) -> anyhow::Result<u64> {
-    let stones: anyhow::Result<HashMap<Stone, u64>> = input
-        .split(' ')
-        .map(|number| {
-            let stone: Stone = number.parse()?;
-
-            Ok((stone, 1))
-        })
+    let mut stones: HashMap<Stone, u64> = parse_stones(input)?
+        .into_iter()
+        .map(|stone| (stone, 1))
         .collect();
-    let mut stones = stones?;
 
     const BLINKS: u32 = 75;
 
@@ -1,23 +1,10 @@
-use std::sync::LazyLock;
-
-use adventofcode::solve_day;
-use anyhow::bail;
+use adventofcode::{parse, solve_day};
 use glam::{DMat2, DVec2, Mat2, Vec2};
-use regex::Regex;
 
 fn main() -> anyhow::Result<()> {
     solve_day(file!(), part1, part2)
 }
 
-static BUTTON_A_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"Button A: X\+(?<x>\d+), Y\+(?<y>\d+)").unwrap());
-
-static BUTTON_B_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"Button B: X\+(?<x>\d+), Y\+(?<y>\d+)").unwrap());
-
-static PRIZE_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"Prize: X=(?<x>\d+), Y=(?<y>\d+)").unwrap());
-
 fn part1(input: &str) -> anyhow::Result<u64> {
     const TOLERANCE: f32 = 0.001;
 
@@ -34,27 +21,8 @@ fn part1(input: &str) -> anyhow::Result<u64> {
 
     let mut total_tokens = 0;
 
-    for raw_machine in input.split("\n\n") {
-        let Some(captures) = BUTTON_A_REGEX.captures(raw_machine) else {
-            bail!("Button A requires an X and Y value.");
-        };
-
-        let ax: f32 = captures["x"].parse()?;
-        let ay: f32 = captures["y"].parse()?;
-
-        let Some(captures) = BUTTON_B_REGEX.captures(raw_machine) else {
-            bail!("Button B requires an X and Y value.");
-        };
-
-        let bx: f32 = captures["x"].parse()?;
-        let by: f32 = captures["y"].parse()?;
-
-        let Some(captures) = PRIZE_REGEX.captures(raw_machine) else {
-            bail!("The prize requiers an X and Y value.");
-        };
-
-        let prize_x: f32 = captures["x"].parse()?;
-        let prize_y: f32 = captures["y"].parse()?;
+    for raw_machine in parse::sections(input) {
+        let [ax, ay, bx, by, prize_x, prize_y]: [f32; 6] = raw_machine.extract()?;
 
         let claw_machine = ClawMachine {
             movement_a: Vec2::new(ax, ay),
@@ -98,27 +66,8 @@ fn part2(input: &str) -> anyhow::Result<u64> {
 
     let mut total_tokens = 0;
 
-    for raw_machine in input.split("\n\n") {
-        let Some(captures) = BUTTON_A_REGEX.captures(raw_machine) else {
-            bail!("Button A requires an X and Y value.");
-        };
-
-        let ax: f64 = captures["x"].parse()?;
-        let ay: f64 = captures["y"].parse()?;
-
-        let Some(captures) = BUTTON_B_REGEX.captures(raw_machine) else {
-            bail!("Button B requires an X and Y value.");
-        };
-
-        let bx: f64 = captures["x"].parse()?;
-        let by: f64 = captures["y"].parse()?;
-
-        let Some(captures) = PRIZE_REGEX.captures(raw_machine) else {
-            bail!("The prize requiers an X and Y value.");
-        };
-
-        let prize_x: f64 = captures["x"].parse()?;
-        let prize_y: f64 = captures["y"].parse()?;
+    for raw_machine in parse::sections(input) {
+        let [ax, ay, bx, by, prize_x, prize_y]: [f64; 6] = raw_machine.extract()?;
 
         let claw_machine = ClawMachine {
             movement_a: DVec2::new(ax, ay),
@@ -1,9 +1,11 @@
-use std::{collections::HashMap, str::FromStr, sync::LazyLock};
+use std::collections::HashMap;
 
-use adventofcode::solve_day;
-use anyhow::{anyhow, bail};
+use adventofcode::{
+    parse::{self, Line, ParseError},
+    solve_day,
+};
+use anyhow::bail;
 use glam::IVec2;
-use regex::Regex;
 
 fn main() -> anyhow::Result<()> {
     solve_day(file!(), part1, part2)
@@ -12,10 +14,6 @@ fn main() -> anyhow::Result<()> {
 const EBHQ_DIMENSIONS: IVec2 = IVec2::new(101, 103);
 const MIDPOINT: IVec2 = IVec2::new(EBHQ_DIMENSIONS.x / 2, EBHQ_DIMENSIONS.y / 2);
 
-static ROBOT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"p=(?<px>-?\d+),(?<py>-?\d+) v=(?<vx>-?\d+),(?<vy>-?\d+)").unwrap()
-});
-
 struct Robot {
     position: IVec2,
     velocity: IVec2,
@@ -29,18 +27,11 @@ enum Quadrant {
     Southwest,
 }
 
-impl FromStr for Robot {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let captures = ROBOT_REGEX
-            .captures(s)
-            .ok_or(anyhow!("Unable to parse robot descriptor."))?;
+impl TryFrom<Line<'_>> for Robot {
+    type Error = ParseError;
 
-        let px: i32 = captures["px"].parse()?;
-        let py: i32 = captures["py"].parse()?;
-        let vx: i32 = captures["vx"].parse()?;
-        let vy: i32 = captures["vy"].parse()?;
+    fn try_from(line: Line<'_>) -> Result<Self, Self::Error> {
+        let [px, py, vx, vy] = line.extract()?;
 
         Ok(Self {
             position: IVec2::new(px, py),
@@ -50,8 +41,9 @@ impl FromStr for Robot {
 }
 
 fn part1(input: &str) -> anyhow::Result<u64> {
-    let robots: anyhow::Result<Vec<Robot>> = input.lines().map(str::parse).collect();
-    let mut robots = robots?;
+    let mut robots: Vec<Robot> = parse::lines(input)
+        .map(Robot::try_from)
+        .collect::<Result<_, _>>()?;
 
     for _ in 0..100 {
         for robot in &mut robots {
@@ -79,8 +71,9 @@ fn part1(input: &str) -> anyhow::Result<u64> {
 }
 
 fn part2(input: &str) -> anyhow::Result<u64> {
-    let robots: anyhow::Result<Vec<Robot>> = input.lines().map(str::parse).collect();
-    let mut robots = robots?;
+    let mut robots: Vec<Robot> = parse::lines(input)
+        .map(Robot::try_from)
+        .collect::<Result<_, _>>()?;
 
     if robots.is_empty() {
         bail!("Empty input detected.");
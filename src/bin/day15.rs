@@ -4,7 +4,10 @@ use std::{
     ops::{Index, IndexMut},
 };
 
-use adventofcode::solve_day;
+use adventofcode::{
+    grid::{Color, Renderer},
+    parse, solve_day,
+};
 use anyhow::anyhow;
 use glam::IVec2;
 
@@ -74,6 +77,16 @@ impl WarehouseMap {
     }
 }
 
+impl PositionType {
+    fn char(&self) -> char {
+        match self {
+            Self::Box => 'O',
+            Self::Empty => '.',
+            Self::Wall => '#',
+        }
+    }
+}
+
 impl TryFrom<char> for PositionType {
     type Error = anyhow::Error;
 
@@ -120,37 +133,43 @@ impl Direction {
     }
 }
 
-fn part1(input: &str) -> anyhow::Result<u64> {
-    let (warehouse_map, movements) = input
-        .split_once("\n\n")
-        .ok_or(anyhow!("Input should contain a double newline."))?;
+fn parse_input(input: &str) -> anyhow::Result<(WarehouseMap, IVec2, Vec<Direction>)> {
+    let [warehouse_map, movements] = parse::split_sections(input)?;
 
     let robot_position = OnceCell::new();
 
-    let mut warehouse_map: WarehouseMap = warehouse_map
-        .lines()
-        .enumerate()
-        .map(|(y, row)| {
-            let robot_position = &robot_position;
+    let warehouse_map: WarehouseMap = warehouse_map
+        .grid(|position, char| {
+            if char == '@' {
+                robot_position
+                    .set(position)
+                    .map_err(|_| anyhow!("Found more than one robot!"))?;
+            }
 
-            row.chars().enumerate().map(move |(x, char)| {
-                if char == '@' {
-                    robot_position.set(IVec2::new(x as i32, y as i32)).unwrap();
-                }
-                PositionType::try_from(char).unwrap()
-            })
-        })
+            PositionType::try_from(char)
+        })?
+        .into_rows()
+        .into_iter()
         .collect();
 
-    let mut robot_position = robot_position
+    let robot_position = robot_position
         .into_inner()
         .ok_or(anyhow!("No robot found when parsing input!"))?;
 
     let movements: Vec<Direction> = movements
-        .replace('\n', "")
-        .chars()
-        .map(|char| Direction::try_from(char).unwrap())
-        .collect();
+        .lines()
+        .flat_map(|line| {
+            line.text().chars().enumerate().map(move |(x, char)| {
+                Direction::try_from(char).map_err(|err| line.error_at(x, err))
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok((warehouse_map, robot_position, movements))
+}
+
+fn part1(input: &str) -> anyhow::Result<u64> {
+    let (mut warehouse_map, mut robot_position, movements) = parse_input(input)?;
 
     for movement in movements {
         let Some(next_empty_space) = warehouse_map.next_empty_space(robot_position, movement)
@@ -169,6 +188,10 @@ fn part1(input: &str) -> anyhow::Result<u64> {
         robot_position = next_position;
     }
 
+    Renderer::new(&warehouse_map.rows, PositionType::char)
+        .mark([robot_position], '@')
+        .debug_print();
+
     let mut gps_sum = 0;
 
     for (y, row) in warehouse_map.rows.into_iter().enumerate() {
@@ -202,6 +225,17 @@ enum BoxSegment {
     Right,
 }
 
+impl WidePositionType {
+    fn char(&self) -> char {
+        match self {
+            Self::Box(BoxSegment::Left) => '[',
+            Self::Box(BoxSegment::Right) => ']',
+            Self::Empty => '.',
+            Self::Wall => '#',
+        }
+    }
+}
+
 impl BoxSegment {
     fn inverse(self) -> Self {
         match self {
@@ -312,33 +346,14 @@ impl WideWarehouseMap {
                                     .push((next_position_right, next_position_right + IVec2::X));
                             }
                             BoxSegment::Right => {
-                                for (y, row) in self.rows.iter().enumerate() {
-                                    for (x, cell) in row.iter().enumerate() {
-                                        let char = match cell {
-                                            WidePositionType::Box(box_segment) => match box_segment
-                                            {
-                                                BoxSegment::Left => '[',
-                                                BoxSegment::Right => {
-                                                    let x = x as i32;
-                                                    let y = y as i32;
-
-                                                    let current_pos = IVec2 { x, y };
-
-                                                    if next_position_right == current_pos {
-                                                        'X'
-                                                    } else {
-                                                        ']'
-                                                    }
-                                                }
-                                            },
-                                            WidePositionType::Empty => '.',
-                                            WidePositionType::Wall => '#',
-                                        };
-
-                                        print!("{char}");
-                                    }
-                                    println!();
-                                }
+                                eprintln!(
+                                    "{}",
+                                    Renderer::new(&self.rows, WidePositionType::char).mark_colored(
+                                        [next_position_right],
+                                        'X',
+                                        Color::Red
+                                    )
+                                );
                                 unreachable!()
                             }
                         },
@@ -411,53 +426,33 @@ impl WideWarehouseMap {
 }
 
 fn part2(input: &str) -> anyhow::Result<u64> {
-    let (warehouse_map, movements) = input
-        .split_once("\n\n")
-        .ok_or(anyhow!("Input should contain a double newline."))?;
-
-    let robot_position = OnceCell::new();
+    let (warehouse_map, robot_position, movements) = parse_input(input)?;
 
     let mut warehouse_map: WideWarehouseMap = warehouse_map
-        .lines()
-        .enumerate()
-        .map(|(y, row)| {
-            let robot_position = &robot_position;
-
-            row.chars()
-                .enumerate()
-                .flat_map(move |(x, char)| match char {
-                    'O' => [
-                        WidePositionType::Box(BoxSegment::Left),
-                        WidePositionType::Box(BoxSegment::Right),
-                    ],
-                    '@' => {
-                        robot_position
-                            .set(IVec2::new((x * 2) as i32, y as i32))
-                            .unwrap();
-
-                        [WidePositionType::Empty, WidePositionType::Empty]
-                    }
-                    '.' => [WidePositionType::Empty, WidePositionType::Empty],
-                    '#' => [WidePositionType::Wall, WidePositionType::Wall],
-                    _ => unreachable!("Invalid position type char detected."),
-                })
+        .rows
+        .iter()
+        .map(|row| {
+            row.iter().flat_map(|position_type| match position_type {
+                PositionType::Box => [
+                    WidePositionType::Box(BoxSegment::Left),
+                    WidePositionType::Box(BoxSegment::Right),
+                ],
+                PositionType::Empty => [WidePositionType::Empty, WidePositionType::Empty],
+                PositionType::Wall => [WidePositionType::Wall, WidePositionType::Wall],
+            })
         })
         .collect();
 
-    let mut robot_position = robot_position
-        .into_inner()
-        .ok_or(anyhow!("No robot found when parsing input!"))?;
-
-    let movements: Vec<Direction> = movements
-        .replace('\n', "")
-        .chars()
-        .map(|char| Direction::try_from(char).unwrap())
-        .collect();
+    let mut robot_position = robot_position * IVec2::new(2, 1);
 
     for movement in movements {
         warehouse_map.try_move(&mut robot_position, movement);
     }
 
+    Renderer::new(&warehouse_map.rows, WidePositionType::char)
+        .mark([robot_position], '@')
+        .debug_print();
+
     let mut gps_sum = 0;
 
     for (y, row) in warehouse_map.rows.into_iter().enumerate() {
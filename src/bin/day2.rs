@@ -1,10 +1,10 @@
-use std::cell::OnceCell;
+use std::{ops::RangeInclusive, str::FromStr};
 
-use adventofcode::solve_day;
+use adventofcode::{parse, report::Table, solve_or_diagnose_day, variant_var};
 use anyhow::{anyhow, bail};
 
 fn main() -> anyhow::Result<()> {
-    solve_day(file!(), part1, part2)
+    solve_or_diagnose_day(file!(), part1, part2, diagnose)
 }
 
 #[derive(Clone, Copy, Eq, PartialEq)]
@@ -27,150 +27,298 @@ impl TryFrom<i64> for ReportDirection {
     }
 }
 
-fn is_safe_diff(level_diff: i64) -> bool {
-    matches!(level_diff, -3..=-1 | 1..=3)
+impl ReportDirection {
+    const ALL: [Self; 2] = [Self::Ascending, Self::Descending];
 }
 
-fn part1(input: &str) -> anyhow::Result<u64> {
-    let mut safe_report_count = 0;
+/// How consistently a report's levels must move in one direction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Monotonicity {
+    /// Every step must move in the report's direction, so a step between two equal levels is
+    /// unsafe.
+    Strict,
+    /// A step between two equal levels is also allowed, as it doesn't break the report's
+    /// direction.
+    NonStrict,
+}
+
+/// The rules deciding whether a report is safe.
+///
+/// The puzzle's rules can be overridden at runtime by setting [`SafetyRules::ENV_VAR`] to e.g.
+/// `steps=1-4,monotonicity=non-strict`. Any omitted rule keeps the puzzle's value, and
+/// the answers are flagged as being for a variant.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct SafetyRules {
+    monotonicity: Monotonicity,
+    /// The allowed sizes of a step between two different levels.
+    step_sizes: RangeInclusive<u64>,
+}
 
-    for report in input.lines() {
-        let levels: Result<Vec<i64>, _> = report.split(' ').map(str::parse).collect();
-        let levels = levels?;
+impl SafetyRules {
+    const ENV_VAR: &str = "AOC_DAY2_RULES";
 
-        let report_length = levels.len();
+    const PUZZLE: Self = Self {
+        monotonicity: Monotonicity::Strict,
+        step_sizes: 1..=3,
+    };
 
-        if report_length == 1 {
-            eprintln!("Very short report detected, count could be considered to be incorrect!");
+    fn from_env() -> anyhow::Result<Self> {
+        match variant_var(Self::ENV_VAR) {
+            Some(rules) => rules.parse(),
+            None => Ok(Self::PUZZLE),
         }
+    }
 
-        let report_direction: OnceCell<ReportDirection> = OnceCell::new();
-        let mut previous_level: Option<i64> = None;
+    /// Whether stepping between two levels with a difference of `level_diff` is safe for a
+    /// report moving in `direction`.
+    fn allows_step(&self, level_diff: i64, direction: ReportDirection) -> bool {
+        let Ok(diff_direction) = ReportDirection::try_from(level_diff) else {
+            return self.monotonicity == Monotonicity::NonStrict;
+        };
 
-        for (index, level) in levels.into_iter().enumerate() {
-            if let Some(previous_level) = previous_level {
-                let level_diff = level - previous_level;
+        diff_direction == direction && self.step_sizes.contains(&level_diff.unsigned_abs())
+    }
+}
 
-                if !is_safe_diff(level_diff) {
-                    break;
-                }
+impl FromStr for SafetyRules {
+    type Err = anyhow::Error;
 
-                let direction = ReportDirection::try_from(level_diff)?;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = Self::PUZZLE;
 
-                if let Some(report_direction) = report_direction.get() {
-                    if direction != *report_direction {
-                        break;
-                    }
+        for setting in parse::settings(s) {
+            let setting = setting?;
 
-                    if index == report_length - 1 {
-                        safe_report_count += 1;
+            match setting.name() {
+                "steps" => rules.step_sizes = setting.range()?,
+                "monotonicity" => {
+                    rules.monotonicity = match setting.value() {
+                        "strict" => Monotonicity::Strict,
+                        "non-strict" => Monotonicity::NonStrict,
+                        _ => bail!("Monotonicity should be `strict` or `non-strict`."),
                     }
-                } else {
-                    let _ = report_direction.set(direction);
                 }
+                name => bail!("Unknown safety rule `{name}`."),
             }
-
-            previous_level = Some(level);
         }
+
+        Ok(rules)
     }
+}
 
-    Ok(safe_report_count)
+struct Report {
+    levels: Vec<i64>,
 }
 
-fn part2(input: &str) -> anyhow::Result<u64> {
-    let mut safe_report_count = 0;
+impl Report {
+    /// The most levels a report can have for [`Report::min_removal_count_brute_force`] to be
+    /// cheap enough to run on every report in diagnostics.
+    const BRUTE_FORCE_MAX_LEVELS: usize = 12;
+
+    fn is_safe(levels: &[i64], rules: &SafetyRules) -> bool {
+        ReportDirection::ALL.into_iter().any(|direction| {
+            levels
+                .windows(2)
+                .all(|window| rules.allows_step(window[1] - window[0], direction))
+        })
+    }
 
-    for report in input.lines() {
-        let levels: Result<Vec<i64>, _> = report.split(' ').map(str::parse).collect();
-        let mut levels = levels?;
+    /// Returns the indices of the fewest levels which need to be removed to make this report
+    /// safe.
+    ///
+    /// Removing levels leaves a subsequence of the report, so this finds the longest safe
+    /// subsequence in each direction, where `longest[i]` is the length of the longest safe
+    /// subsequence ending with level `i`.
+    fn min_removals(&self, rules: &SafetyRules) -> Vec<usize> {
+        let level_count = self.levels.len();
+
+        let mut best_kept_indices: Vec<usize> = Vec::new();
+
+        for direction in ReportDirection::ALL {
+            let mut longest: Vec<usize> = vec![1; level_count];
+            let mut previous_kept: Vec<Option<usize>> = vec![None; level_count];
+
+            for index in 0..level_count {
+                for previous_index in 0..index {
+                    let level_diff = self.levels[index] - self.levels[previous_index];
+
+                    if rules.allows_step(level_diff, direction)
+                        && longest[previous_index] + 1 > longest[index]
+                    {
+                        longest[index] = longest[previous_index] + 1;
+                        previous_kept[index] = Some(previous_index);
+                    }
+                }
+            }
 
-        let report_length = levels.len();
+            let Some(last_kept) = (0..level_count).max_by_key(|&index| longest[index]) else {
+                continue;
+            };
+
+            if longest[last_kept] <= best_kept_indices.len() {
+                continue;
+            }
 
-        if report_length < 4 {
-            bail!("Very short report detected, direction could not be determined!");
+            best_kept_indices =
+                std::iter::successors(Some(last_kept), |&index| previous_kept[index]).collect();
         }
 
-        let first_levels: Vec<ReportDirection> = levels[..4]
-            .windows(2)
-            .flat_map(|level_window| {
-                let &[level, next_level] = level_window else {
-                    unreachable!()
-                };
+        (0..level_count)
+            .filter(|index| !best_kept_indices.contains(index))
+            .collect()
+    }
 
-                ReportDirection::try_from(next_level - level)
+    /// The number of removals needed to make this report safe, found by trying every possible
+    /// set of removals, smallest first. Used to check [`Report::min_removals`].
+    fn min_removal_count_brute_force(&self, rules: &SafetyRules) -> usize {
+        let level_count = self.levels.len();
+
+        (0..=level_count)
+            .find(|&removal_count| {
+                (0_u64..(1 << level_count))
+                    .filter(|removal_bitset| removal_bitset.count_ones() as usize == removal_count)
+                    .any(|removal_bitset| {
+                        let levels: Vec<i64> = self
+                            .levels
+                            .iter()
+                            .enumerate()
+                            .filter(|&(index, _)| removal_bitset & (1 << index) == 0)
+                            .map(|(_, &level)| level)
+                            .collect();
+
+                        Self::is_safe(&levels, rules)
+                    })
             })
-            .collect();
+            .unwrap_or(level_count)
+    }
+}
 
-        let report_direction = if first_levels
-            .iter()
-            .filter(|&&direction| direction == ReportDirection::Ascending)
-            .count()
-            > 1
-        {
-            ReportDirection::Ascending
-        } else if first_levels
-            .iter()
-            .filter(|&&direction| direction == ReportDirection::Descending)
-            .count()
-            > 1
-        {
-            ReportDirection::Descending
-        } else {
-            continue;
-        };
+fn parse_reports(input: &str) -> anyhow::Result<Vec<Report>> {
+    parse::lines(input)
+        .map(|line| {
+            Ok(Report {
+                levels: line.parse_split(' ')?,
+            })
+        })
+        .collect()
+}
 
-        let is_safe = |level_diff| {
-            is_safe_diff(level_diff)
-                && ReportDirection::try_from(level_diff)
-                    .is_ok_and(|direction| direction == report_direction)
-        };
+/// Counts the reports which can be made safe by removing at most `max_removals` levels.
+fn count_safe_reports(input: &str, max_removals: usize) -> anyhow::Result<u64> {
+    let rules = SafetyRules::from_env()?;
 
-        struct RemovedLevel {
-            first_try: bool,
-            index: usize,
-            value: i64,
-        }
+    let safe_report_count = parse_reports(input)?
+        .into_iter()
+        .filter(|report| report.min_removals(&rules).len() <= max_removals)
+        .count();
 
-        let mut removed_level: Option<RemovedLevel> = None;
+    Ok(safe_report_count as u64)
+}
 
-        let mut current_index = 0;
+fn part1(input: &str) -> anyhow::Result<u64> {
+    count_safe_reports(input, 0)
+}
 
-        while current_index < levels.len() - 1 {
-            let [level, next_level] = levels[current_index..(current_index + 2)] else {
-                unreachable!();
-            };
+fn part2(input: &str) -> anyhow::Result<u64> {
+    count_safe_reports(input, 1)
+}
 
-            if !is_safe(next_level - level) {
-                match &mut removed_level {
-                    Some(removed_level) if !removed_level.first_try => break,
-                    Some(removed_level) => {
-                        levels[removed_level.index] = removed_level.value;
+/// Lists the minimum removals needed for each report, alongside the brute force oracle's count
+/// where the report is short enough to check.
+fn diagnose(input: &str) -> anyhow::Result<Vec<Table>> {
+    let mut table = Table::new(
+        "Minimum removals per report",
+        ["Line", "Levels", "Removals", "Removed indices", "Oracle"],
+    );
 
-                        removed_level.first_try = false;
+    let rules = SafetyRules::from_env()?;
 
-                        current_index = 0;
-                    }
-                    None => {
-                        levels.remove(current_index);
+    for (index, report) in parse_reports(input)?.into_iter().enumerate() {
+        let removed_indices = report.min_removals(&rules);
+
+        let oracle = if report.levels.len() <= Report::BRUTE_FORCE_MAX_LEVELS {
+            report.min_removal_count_brute_force(&rules).to_string()
+        } else {
+            String::from("-")
+        };
 
-                        removed_level = Some(RemovedLevel {
-                            first_try: true,
-                            index: current_index,
-                            value: level,
-                        });
+        let levels: Vec<String> = report.levels.iter().map(i64::to_string).collect();
+        let removed: Vec<String> = removed_indices.iter().map(usize::to_string).collect();
 
-                        current_index = 0;
-                    }
-                }
-            } else {
-                current_index += 1;
-            }
+        table.push_row([
+            (index + 1).to_string(),
+            levels.join(" "),
+            removed_indices.len().to_string(),
+            removed.join(" "),
+            oracle,
+        ]);
+    }
+
+    Ok(vec![table])
+}
 
-            if current_index == levels.len() - 1 {
-                safe_report_count += 1;
+#[cfg(test)]
+mod tests {
+    use fastrand::Rng;
+
+    use super::*;
+
+    const RULE_VARIANTS: [SafetyRules; 3] = [
+        SafetyRules::PUZZLE,
+        SafetyRules {
+            monotonicity: Monotonicity::NonStrict,
+            step_sizes: 2..=4,
+        },
+        SafetyRules {
+            monotonicity: Monotonicity::NonStrict,
+            step_sizes: 1..=1,
+        },
+    ];
+
+    fn random_report(rng: &mut Rng) -> Report {
+        let mut level = rng.i64(1..=20);
+
+        let levels = (0..rng.usize(0..=8))
+            .map(|_| {
+                level += rng.i64(-5..=5);
+                level
+            })
+            .collect();
+
+        Report { levels }
+    }
+
+    #[test]
+    fn min_removals_matches_brute_force() {
+        let mut rng = Rng::with_seed(2);
+
+        for _ in 0..2000 {
+            let report = random_report(&mut rng);
+
+            for rules in &RULE_VARIANTS {
+                let removed_indices = report.min_removals(rules);
+
+                assert_eq!(
+                    removed_indices.len(),
+                    report.min_removal_count_brute_force(rules),
+                    "Report {:?} with rules {rules:?}",
+                    report.levels
+                );
+
+                let kept_levels: Vec<i64> = report
+                    .levels
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| !removed_indices.contains(index))
+                    .map(|(_, &level)| level)
+                    .collect();
+
+                assert!(
+                    Report::is_safe(&kept_levels, rules),
+                    "Report {:?} with rules {rules:?} is unsafe after removing {removed_indices:?}",
+                    report.levels
+                );
             }
         }
     }
-
-    Ok(safe_report_count)
 }
@@ -1,38 +1,435 @@
-use adventofcode::solve_day;
-use regex::Regex;
+use std::ops::RangeInclusive;
+
+use adventofcode::{parse, report::Table, solve_or_diagnose_day, variant_var};
+use anyhow::{anyhow, bail, ensure};
 
 fn main() -> anyhow::Result<()> {
-    solve_day(file!(), part1, part2)
+    solve_or_diagnose_day(file!(), part1, part2, diagnose)
 }
 
-fn part1(input: &str) -> anyhow::Result<u64> {
-    let regex = Regex::new(r"mul\((\d+),(\d+)\)").unwrap();
+/// What an instruction does when it's executed.
+#[derive(Clone, Copy, Debug)]
+enum Operation {
+    /// Enables value-producing instructions.
+    Enable,
+    /// Disables value-producing instructions.
+    Disable,
+    /// Produces a value from the instruction's arguments, which is added to the program output,
+    /// or `None` if the value can't be represented, e.g. a negative difference.
+    Value(fn(&[u64]) -> Option<u64>),
+}
+
+impl Operation {
+    /// Looks up an operation by the name used for it in [`InstructionSet::ENV_VAR`].
+    fn from_name(name: &str) -> anyhow::Result<Self> {
+        match name {
+            "enable" => Ok(Self::Enable),
+            "disable" => Ok(Self::Disable),
+            "sum" => Ok(Self::Value(sum)),
+            "difference" => Ok(Self::Value(difference)),
+            "product" => Ok(Self::Value(product)),
+            _ => bail!(
+                "Operation `{name}` should be `enable`, `disable`, `sum`, `difference` or \
+                 `product`."
+            ),
+        }
+    }
+}
+
+fn sum(arguments: &[u64]) -> Option<u64> {
+    arguments
+        .iter()
+        .try_fold(0_u64, |sum, &argument| sum.checked_add(argument))
+}
+
+/// The first argument minus all of the others.
+fn difference(arguments: &[u64]) -> Option<u64> {
+    let (&first, rest) = arguments.split_first()?;
+
+    rest.iter().try_fold(first, |difference, &argument| {
+        difference.checked_sub(argument)
+    })
+}
+
+fn product(arguments: &[u64]) -> Option<u64> {
+    arguments
+        .iter()
+        .try_fold(1_u64, |product, &argument| product.checked_mul(argument))
+}
+
+/// A kind of instruction recognised by the scanner, written as `name(argument,argument,...)`.
+struct InstructionSpec {
+    /// How many arguments the instruction takes.
+    argument_count: RangeInclusive<usize>,
+    /// How many digits each argument may have.
+    digit_count: RangeInclusive<usize>,
+    name: String,
+    operation: Operation,
+}
+
+/// The instructions which the scanner looks for in corrupted memory.
+///
+/// Extra instructions can be registered, or the puzzle's instructions redefined, at runtime by
+/// setting [`InstructionSet::ENV_VAR`] to a comma separated list of specs written
+/// `name:arguments:digits:operation`, e.g. `add:2-5:1-3:sum,mul:2:1-4:product`. The argument and
+/// digit counts are ranges as read by [`parse::range`], and the operation is named as in
+/// [`Operation::from_name`].
+struct InstructionSet {
+    specs: Vec<InstructionSpec>,
+}
+
+impl InstructionSet {
+    const ENV_VAR: &str = "AOC_DAY3_INSTRUCTIONS";
+
+    /// The instructions described by the puzzle: `mul` with 2 arguments of 1-3 digits each, plus
+    /// `do()` and `don't()`.
+    fn puzzle() -> Self {
+        let mut instruction_set = Self { specs: Vec::new() };
+
+        instruction_set
+            .register("do", 0..=0, 0..=0, Operation::Enable)
+            .register("don't", 0..=0, 0..=0, Operation::Disable)
+            .register("mul", 2..=2, 1..=3, Operation::Value(product));
+
+        instruction_set
+    }
+
+    fn from_env() -> anyhow::Result<Self> {
+        let mut instruction_set = Self::puzzle();
 
-    let mut program_output = 0;
+        let Some(specs) = variant_var(Self::ENV_VAR) else {
+            return Ok(instruction_set);
+        };
 
-    for (_, [multiplicand_a, multiplicand_b]) in regex
-        .captures_iter(input)
-        .map(|captures| captures.extract())
-    {
-        let multiplicand_a: u64 = multiplicand_a.parse()?;
-        let multiplicand_b: u64 = multiplicand_b.parse()?;
+        for spec in specs
+            .split(',')
+            .map(str::trim)
+            .filter(|spec| !spec.is_empty())
+        {
+            let [name, argument_count, digit_count, operation] = spec
+                .split(':')
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .try_into()
+                .map_err(|_| {
+                    anyhow!(
+                        "Instruction `{spec}` should be written `name:arguments:digits:operation`."
+                    )
+                })?;
 
-        program_output += multiplicand_a * multiplicand_b;
+            ensure!(
+                !name.is_empty() && !name.contains(['(', ')', ',']),
+                "Instruction name `{name}` should be non-empty, without parentheses or commas."
+            );
+
+            instruction_set.register(
+                name,
+                parse::range(argument_count)?,
+                parse::range(digit_count)?,
+                Operation::from_name(operation)?,
+            );
+        }
+
+        Ok(instruction_set)
+    }
+
+    /// Adds an instruction to the set, replacing any existing instruction with the same name.
+    fn register(
+        &mut self,
+        name: &str,
+        argument_count: RangeInclusive<usize>,
+        digit_count: RangeInclusive<usize>,
+        operation: Operation,
+    ) -> &mut Self {
+        let spec = InstructionSpec {
+            argument_count,
+            digit_count,
+            name: String::from(name),
+            operation,
+        };
+
+        match self.specs.iter_mut().find(|existing| existing.name == name) {
+            Some(existing) => *existing = spec,
+            None => self.specs.push(spec),
+        }
+
+        self
     }
 
-    Ok(program_output)
+    /// Whether `char` could be the start of an instruction.
+    fn starts_instruction(&self, char: char) -> bool {
+        self.specs.iter().any(|spec| spec.name.starts_with(char))
+    }
+
+    /// Parses the instruction at the very start of `memory`, returning it along with its length.
+    fn instruction_at(&self, memory: &str) -> Option<(Instruction, usize)> {
+        self.specs
+            .iter()
+            .enumerate()
+            .find_map(|(spec_index, spec)| {
+                let rest = memory.strip_prefix(spec.name.as_str())?.strip_prefix('(')?;
+                let (arguments, rest) = Self::arguments(spec, rest)?;
+
+                Some((
+                    Instruction {
+                        arguments,
+                        spec_index,
+                    },
+                    memory.len() - rest.len(),
+                ))
+            })
+    }
+
+    /// Parses a comma separated argument list and its closing parenthesis from the start of
+    /// `memory`, returning the arguments and the remaining memory.
+    fn arguments<'a>(spec: &InstructionSpec, memory: &'a str) -> Option<(Vec<u64>, &'a str)> {
+        let mut arguments = Vec::new();
+
+        let rest = match memory.strip_prefix(')') {
+            Some(rest) => rest,
+            None => {
+                let mut rest = memory;
+
+                loop {
+                    let digit_count = rest.bytes().take_while(u8::is_ascii_digit).count();
+
+                    if !spec.digit_count.contains(&digit_count) {
+                        return None;
+                    }
+
+                    arguments.push(rest[..digit_count].parse().ok()?);
+                    rest = &rest[digit_count..];
+
+                    match rest.as_bytes().first()? {
+                        b',' => rest = &rest[1..],
+                        b')' => break &rest[1..],
+                        _ => return None,
+                    }
+                }
+            }
+        };
+
+        spec.argument_count
+            .contains(&arguments.len())
+            .then_some((arguments, rest))
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Instruction {
+    arguments: Vec<u64>,
+    /// The index of the instruction's spec within its [`InstructionSet`].
+    spec_index: usize,
+}
+
+/// An instruction found in corrupted memory, along with where it was found.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Token<'a> {
+    instruction: Instruction,
+    offset: usize,
+    text: &'a str,
+}
+
+/// Finds every uncorrupted instruction in memory, skipping over everything else.
+struct Scanner<'a> {
+    instruction_set: &'a InstructionSet,
+    memory: &'a str,
+    offset: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(instruction_set: &'a InstructionSet, memory: &'a str) -> Self {
+        Self {
+            instruction_set,
+            memory,
+            offset: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Scanner<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // We can skip straight past anything which can't be the start of an instruction.
+            let candidate_offset = self.offset
+                + self.memory[self.offset..]
+                    .find(|char| self.instruction_set.starts_instruction(char))?;
+
+            match self
+                .instruction_set
+                .instruction_at(&self.memory[candidate_offset..])
+            {
+                Some((instruction, length)) => {
+                    self.offset = candidate_offset + length;
+
+                    return Some(Token {
+                        instruction,
+                        offset: candidate_offset,
+                        text: &self.memory[candidate_offset..self.offset],
+                    });
+                }
+                None => self.offset = candidate_offset + 1,
+            }
+        }
+    }
+}
+
+/// What happened to an instruction when the program reached it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Outcome {
+    /// The instruction switched value-producing instructions on or off.
+    Switched,
+    /// The instruction's value was added to the output.
+    Executed(u64),
+    /// The instruction was skipped because value-producing instructions were disabled.
+    Disabled(u64),
+    /// The instruction was skipped because its value couldn't be represented.
+    Invalid,
+}
+
+/// The outputs of running a program, both with and without honoring `do()` and `don't()`.
+#[derive(Default)]
+struct ProgramOutput<'a> {
+    /// The sum of every value produced while value-producing instructions were enabled.
+    enabled: u64,
+    /// Every instruction in the program, along with what happened to it.
+    trace: Vec<(Token<'a>, Outcome)>,
+    /// The sum of every value produced by the program.
+    total: u64,
+}
+
+/// Runs the program in `memory`, or returns an error if either of its outputs overflows.
+fn run<'a>(
+    instruction_set: &'a InstructionSet,
+    memory: &'a str,
+) -> anyhow::Result<ProgramOutput<'a>> {
+    let mut output = ProgramOutput::default();
+    let mut enabled = true;
+
+    for token in Scanner::new(instruction_set, memory) {
+        let outcome = match instruction_set.specs[token.instruction.spec_index].operation {
+            Operation::Enable => {
+                enabled = true;
+                Outcome::Switched
+            }
+            Operation::Disable => {
+                enabled = false;
+                Outcome::Switched
+            }
+            Operation::Value(value) => match value(&token.instruction.arguments) {
+                Some(value) => {
+                    output.total = output.total.checked_add(value).ok_or(anyhow!(
+                        "The program output overflowed at offset {}.",
+                        token.offset
+                    ))?;
+
+                    if enabled {
+                        // The enabled output can't exceed the total, so it can't overflow.
+                        output.enabled += value;
+                        Outcome::Executed(value)
+                    } else {
+                        Outcome::Disabled(value)
+                    }
+                }
+                None => Outcome::Invalid,
+            },
+        };
+
+        output.trace.push((token, outcome));
+    }
+
+    Ok(output)
+}
+
+fn part1(input: &str) -> anyhow::Result<u64> {
+    Ok(run(&InstructionSet::from_env()?, input)?.total)
 }
 
 fn part2(input: &str) -> anyhow::Result<u64> {
-    let input = input.replace('\n', "");
-    let input = format!("do(){input}don't()");
+    Ok(run(&InstructionSet::from_env()?, input)?.enabled)
+}
+
+/// Lists every instruction found in memory, and whether it was executed or skipped.
+fn diagnose(input: &str) -> anyhow::Result<Vec<Table>> {
+    let instruction_set = InstructionSet::from_env()?;
+    let output = run(&instruction_set, input)?;
+
+    let mut table = Table::new(
+        "Instruction trace",
+        ["Offset", "Instruction", "Outcome", "Value"],
+    );
+
+    for (token, outcome) in &output.trace {
+        let (outcome, value) = match outcome {
+            Outcome::Switched => ("switched", String::new()),
+            Outcome::Executed(value) => ("executed", value.to_string()),
+            Outcome::Disabled(value) => ("skipped (disabled)", value.to_string()),
+            Outcome::Invalid => ("skipped (invalid)", String::new()),
+        };
+
+        table.push_row([
+            token.offset.to_string(),
+            token.text.to_string(),
+            outcome.to_string(),
+            value,
+        ]);
+    }
+
+    table.set_footer([
+        String::from("Total"),
+        String::new(),
+        format!("enabled: {}", output.enabled),
+        output.total.to_string(),
+    ]);
+
+    Ok(vec![table])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let regex = Regex::new(r"(?U)(?:do\(\).+don't\(\))+").unwrap();
+    #[test]
+    fn instructions_do_not_span_lines() {
+        let instruction_set = InstructionSet::puzzle();
 
-    let parsed_input: String = regex
-        .find_iter(&input)
-        .flat_map(|r#match| r#match.as_str().chars())
-        .collect();
+        // Joining the lines would invent a `mul(12,3)`, but the newline corrupts it.
+        let output = run(&instruction_set, "xmul(2,4)mul(1\n2,3)mul(3,3)").unwrap();
 
-    part1(&parsed_input)
+        let texts: Vec<&str> = output.trace.iter().map(|(token, _)| token.text).collect();
+
+        assert_eq!(texts, ["mul(2,4)", "mul(3,3)"]);
+        assert_eq!((output.total, output.enabled), (17, 17));
+    }
+
+    #[test]
+    fn enabled_state_carries_across_lines() {
+        let instruction_set = InstructionSet::puzzle();
+
+        let output = run(
+            &instruction_set,
+            "mul(2,2)don't()\nmul(3,3)do\n()mul(4,4)\ndo()mul(5,5)",
+        )
+        .unwrap();
+
+        assert_eq!(output.total, 4 + 9 + 16 + 25);
+        assert_eq!(output.enabled, 4 + 25);
+    }
+
+    #[test]
+    fn overflowing_output_is_an_error() {
+        let mut instruction_set = InstructionSet::puzzle();
+        instruction_set.register("mul", 2..=2, 1..=20, Operation::Value(product));
+
+        // Each product fits in a `u64`, but their sum doesn't.
+        assert!(run(&instruction_set, "mul(4294967295,4294967297)mul(1,1)").is_err());
+
+        // An overflowing product is skipped rather than an error.
+        let output = run(&instruction_set, "mul(4294967296,4294967296)mul(1,1)").unwrap();
+
+        assert_eq!(output.trace[0].1, Outcome::Invalid);
+        assert_eq!(output.total, 1);
+    }
 }
@@ -1,13 +1,18 @@
-use std::ops::Index;
-
-use adventofcode::solve_day;
+use std::collections::HashSet;
+
+use adventofcode::{
+    env_choice,
+    grid::{self, Color, Grid},
+    parse::{self, ParseError},
+    solve_day,
+};
 use glam::IVec2;
 
 fn main() -> anyhow::Result<()> {
     solve_day(file!(), part1, part2)
 }
 
-#[derive(Clone, Copy, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum Direction {
     East,
     North,
@@ -31,19 +36,6 @@ impl Direction {
         Direction::West,
     ];
 
-    fn opposite(self) -> Self {
-        match self {
-            Self::East => Self::West,
-            Self::North => Self::South,
-            Self::Northeast => Self::Southwest,
-            Self::Northwest => Self::Southeast,
-            Self::South => Self::North,
-            Self::Southeast => Self::Northwest,
-            Self::Southwest => Self::Northeast,
-            Self::West => Self::East,
-        }
-    }
-
     /// Returns the amount needed to move in the x and y axes to move in this direction.
     /// Note that -y is up, because we iterate down through the wordsearch.
     fn xy(self) -> IVec2 {
@@ -60,136 +52,231 @@ impl Direction {
     }
 }
 
-struct WordSearch {
-    col_count: i32,
-    rows: Vec<Vec<char>>,
-    row_count: i32,
+type WordSearch = Grid<char>;
+
+fn parse_word_search(input: &str) -> Result<WordSearch, ParseError> {
+    parse::grid(parse::lines(input), |_, char| Ok::<_, &str>(char))
 }
 
-impl Index<usize> for WordSearch {
-    type Output = Vec<char>;
+/// A word found in a word search, reading from `start` in `direction`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Occurrence<'a> {
+    direction: Direction,
+    start: IVec2,
+    word: &'a str,
+}
 
-    fn index(&self, index: usize) -> &Self::Output {
-        self.rows.index(index)
+impl Occurrence<'_> {
+    /// The positions of each of the word's chars, in order.
+    fn positions(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (0..self.word.chars().count() as i32).map(|index| self.start + self.direction.xy() * index)
     }
 }
 
-impl<I2: IntoIterator<Item = char>> FromIterator<I2> for WordSearch {
-    fn from_iter<I1>(iter: I1) -> Self
-    where
-        I1: IntoIterator<Item = I2>,
-    {
-        let rows: Vec<Vec<char>> = iter
-            .into_iter()
-            .map(|char_iter| char_iter.into_iter().collect())
-            .collect();
-
-        assert!(!rows.is_empty(), "No rows in word search.");
+/// Finds every occurrence of each of `words` in the word search, reading in any of the 8
+/// directions. Single char words are only found once per position, rather than once per
+/// direction.
+fn find_words<'a>(word_search: &WordSearch, words: &[&'a str]) -> Vec<Occurrence<'a>> {
+    let mut occurrences = Vec::new();
 
-        let row_count = rows.len();
-        let col_count = rows[0].len();
+    for (start, &start_char) in word_search.iter() {
+        for &word in words {
+            let mut chars = word.chars();
 
-        assert!(
-            rows[1..].iter().all(|row| row.len() == col_count),
-            "Word search rows are not all of equal length."
-        );
+            if chars.next() != Some(start_char) {
+                continue;
+            }
 
-        Self {
-            col_count: col_count as i32,
-            rows,
-            row_count: row_count as i32,
+            let directions = if chars.clone().next().is_none() {
+                &Direction::ALL[..1]
+            } else {
+                &Direction::ALL[..]
+            };
+
+            for &direction in directions {
+                let matches = chars.clone().zip(1..).all(|(char, index)| {
+                    word_search.get(start + direction.xy() * index) == Some(&char)
+                });
+
+                if matches {
+                    occurrences.push(Occurrence {
+                        direction,
+                        start,
+                        word,
+                    });
+                }
+            }
         }
     }
+
+    occurrences
 }
 
-fn part1(input: &str) -> anyhow::Result<u64> {
-    const XMAS: [char; 4] = ['X', 'M', 'A', 'S'];
+/// A 2D shape of chars to look for in a word search, where [`Pattern::WILDCARD`] cells match any
+/// char.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Pattern {
+    /// The offset of each non-wildcard cell from the pattern's top left corner, in reading order.
+    cells: Vec<(IVec2, char)>,
+    size: IVec2,
+}
 
-    fn valid_directions(word_search: &WordSearch, cell: IVec2) -> Vec<Direction> {
-        Direction::ALL
-            .into_iter()
-            .filter(|direction| {
-                let offset = direction.xy() * (XMAS.len() as i32 - 1);
+impl Pattern {
+    const WILDCARD: char = '.';
 
-                let x_bounds = 0..word_search.col_count;
-                let y_bounds = 0..word_search.row_count;
+    /// Two `MAS`es crossing diagonally at their `A`s.
+    const X_MAS: &str = "M.S\n.A.\nM.S";
 
-                let final_position = cell + offset;
+    fn new(shape: &str) -> Result<Self, ParseError> {
+        let grid = parse::grid(parse::lines(shape), |_, char| Ok::<_, &str>(char))?;
 
-                x_bounds.contains(&final_position.x) && y_bounds.contains(&final_position.y)
-            })
-            .collect()
+        let cells = grid
+            .iter()
+            .filter(|&(_, &char)| char != Self::WILDCARD)
+            .map(|(offset, &char)| (offset, char))
+            .collect();
+
+        Ok(Self {
+            cells,
+            size: IVec2::new(grid.col_count(), grid.row_count()),
+        })
     }
 
-    let word_search: WordSearch = input.lines().map(|row| row.chars()).collect();
+    /// This pattern rotated a quarter turn clockwise.
+    fn rotated(&self) -> Self {
+        let mut cells: Vec<(IVec2, char)> = self
+            .cells
+            .iter()
+            .map(|&(offset, char)| (IVec2::new(self.size.y - 1 - offset.y, offset.x), char))
+            .collect();
 
-    let mut xmas_count = 0;
+        cells.sort_unstable_by_key(|&(offset, _)| (offset.y, offset.x));
 
-    for (y, row) in word_search.rows.iter().enumerate() {
-        for x in row
-            .iter()
-            .enumerate()
-            .filter_map(|(x, &char)| (char == 'X').then_some(x))
-        {
-            let cell = IVec2::new(x as i32, y as i32);
-
-            for direction in valid_directions(&word_search, cell) {
-                for (xmas_index, &xmas_char) in XMAS[1..].iter().enumerate() {
-                    let xmas_index = (xmas_index + 1) as i32;
-
-                    let next_cell = cell + direction.xy() * xmas_index;
-                    let next_char = word_search[next_cell.y as usize][next_cell.x as usize];
-
-                    if next_char != xmas_char {
-                        break;
-                    } else if xmas_char == 'S' {
-                        xmas_count += 1;
-                    }
-                }
+        Self {
+            cells,
+            size: IVec2::new(self.size.y, self.size.x),
+        }
+    }
+
+    /// Every distinct quarter turn rotation of this pattern, starting with the pattern itself.
+    fn rotations(&self) -> Vec<Self> {
+        let mut rotations = vec![self.clone()];
+
+        for _ in 0..3 {
+            let rotation = rotations[rotations.len() - 1].rotated();
+
+            if !rotations.contains(&rotation) {
+                rotations.push(rotation);
             }
         }
+
+        rotations
     }
 
-    Ok(xmas_count)
+    /// The positions of this pattern's non-wildcard cells when it appears at `start`.
+    fn positions(&self, start: IVec2) -> impl Iterator<Item = IVec2> + '_ {
+        self.cells.iter().map(move |&(offset, _)| start + offset)
+    }
+
+    /// The top left positions at which this pattern appears in the word search.
+    fn matches<'a>(&'a self, word_search: &'a WordSearch) -> impl Iterator<Item = IVec2> + 'a {
+        let last_start = IVec2::new(word_search.col_count(), word_search.row_count()) - self.size;
+
+        (0..=last_start.y)
+            .flat_map(move |y| (0..=last_start.x).map(move |x| IVec2::new(x, y)))
+            .filter(|&start| {
+                self.cells
+                    .iter()
+                    .all(|&(offset, char)| word_search[start + offset] == char)
+            })
+    }
 }
 
-fn part2(input: &str) -> anyhow::Result<u64> {
-    let word_search: WordSearch = input.lines().map(|row| row.chars()).collect();
+/// How matched cells are picked out when the word search is debug rendered.
+///
+/// Defaults to [`HighlightStyle::Color`], and can be overridden by setting
+/// [`HighlightStyle::ENV_VAR`] to `color` or `dots`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum HighlightStyle {
+    /// Matched cells are colored, and every other cell is dimmed.
+    Color,
+    /// Every unmatched cell is replaced with a `.`, without using any colors.
+    Dots,
+}
 
-    let mut x_mas_count = 0;
+impl HighlightStyle {
+    const ENV_VAR: &str = "AOC_DAY4_HIGHLIGHT";
 
-    for (y, row) in word_search.rows.iter().enumerate() {
-        for x in row.iter().enumerate().filter_map(|(x, &char)| {
-            let x = x as i32;
-            let y = y as i32;
+    fn from_env() -> anyhow::Result<Self> {
+        env_choice(
+            Self::ENV_VAR,
+            "Highlight style",
+            &[("color", Self::Color), ("dots", Self::Dots)],
+        )
+    }
+}
 
-            let in_bounds =
-                || x > 0 && x < word_search.col_count - 1 && y > 0 && y < word_search.row_count - 1;
+/// Prints the word search with every matched cell highlighted, if debug rendering is enabled.
+fn debug_print_matches(
+    word_search: &WordSearch,
+    matched_positions: impl IntoIterator<Item = IVec2>,
+) -> anyhow::Result<()> {
+    if !grid::debug_enabled() {
+        return Ok(());
+    }
 
-            (char == 'A' && in_bounds()).then_some(x)
-        }) {
-            let cell = IVec2::new(x, y as i32);
+    let matched_positions: HashSet<IVec2> = matched_positions.into_iter().collect();
+    let renderer = word_search.render(|&char| char).rulers(true);
+
+    match HighlightStyle::from_env()? {
+        HighlightStyle::Color => renderer
+            .background(Color::Dim)
+            .highlight(matched_positions, Color::Green),
+        HighlightStyle::Dots => renderer.color(false).mark(
+            word_search
+                .positions()
+                .filter(|position| !matched_positions.contains(position)),
+            '.',
+        ),
+    }
+    .debug_print();
 
-            for direction in [Direction::Northeast, Direction::Northwest] {
-                let adjacent_cell = cell + direction.xy();
-                let adjacent_char = word_search[adjacent_cell.y as usize][adjacent_cell.x as usize];
+    Ok(())
+}
 
-                let opposite_adjacent_cell = cell + direction.opposite().xy();
-                let opposite_adjacent_char = word_search[opposite_adjacent_cell.y as usize]
-                    [opposite_adjacent_cell.x as usize];
+fn part1(input: &str) -> anyhow::Result<u64> {
+    let word_search = parse_word_search(input)?;
 
-                if !((adjacent_char == 'M' && opposite_adjacent_char == 'S')
-                    || (adjacent_char == 'S' && opposite_adjacent_char == 'M'))
-                {
-                    break;
-                }
+    let occurrences = find_words(&word_search, &["XMAS"]);
 
-                if direction == Direction::Northwest {
-                    x_mas_count += 1;
-                }
-            }
-        }
-    }
+    debug_print_matches(
+        &word_search,
+        occurrences.iter().flat_map(Occurrence::positions),
+    )?;
+
+    Ok(occurrences.len() as u64)
+}
+
+fn part2(input: &str) -> anyhow::Result<u64> {
+    let word_search = parse_word_search(input)?;
+
+    let rotations = Pattern::new(Pattern::X_MAS)?.rotations();
+
+    let matches: Vec<(&Pattern, IVec2)> = rotations
+        .iter()
+        .flat_map(|rotation| {
+            rotation
+                .matches(&word_search)
+                .map(move |start| (rotation, start))
+        })
+        .collect();
+
+    debug_print_matches(
+        &word_search,
+        matches
+            .iter()
+            .flat_map(|&(rotation, start)| rotation.positions(start)),
+    )?;
 
-    Ok(x_mas_count)
+    Ok(matches.len() as u64)
 }
@@ -1,173 +1,650 @@
-use std::collections::{HashMap, HashSet, VecDeque};
-
-use adventofcode::solve_day;
-use anyhow::{anyhow, bail};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, hint,
+    time::{Duration, Instant},
+};
+
+use adventofcode::{
+    parse::{self, Line, ParseError, Section},
+    report::Table,
+    solve_or_diagnose_day,
+};
+use petgraph::{
+    algo::{tarjan_scc, toposort},
+    graphmap::DiGraphMap,
+};
 
 fn main() -> anyhow::Result<()> {
-    solve_day(file!(), part1, part2)
+    solve_or_diagnose_day(file!(), part1, part2, diagnose)
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 struct Page(u64);
 
-fn part1(input: &str) -> anyhow::Result<u64> {
-    let (ordering_rules_raw, updates) = input
-        .split_once("\n\n")
-        .ok_or(anyhow!("Input is not comprised of 2 sections."))?;
+impl fmt::Display for Page {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A set of pages which the ordering rules for an update require to come before each other, so
+/// the update can't be ordered.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct PageCycle(Vec<Page>);
 
-    let mut ordering_rules: HashMap<Page, HashSet<Page>> = HashMap::new();
+impl fmt::Display for PageCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pages: Vec<String> = self.0.iter().map(Page::to_string).collect();
 
-    for ordering_rule in ordering_rules_raw.lines() {
-        let (earlier_page, later_page) = ordering_rule
-            .split_once('|')
-            .ok_or(anyhow!("Ordering rule does not have a separator."))?;
+        write!(
+            f,
+            "Ordering rules form a cycle between pages {}.",
+            pages.join(", ")
+        )
+    }
+}
+
+/// A rule that the first page must come before the second, if both are in an update.
+type Rule = (Page, Page);
+
+struct OrderingRules {
+    /// The pages which must come after each page, if both are in an update.
+    later_pages: HashMap<Page, HashSet<Page>>,
+    /// The line numbers on which each rule appears.
+    rule_lines: HashMap<Rule, Vec<usize>>,
+}
 
-        let earlier_page = earlier_page.parse()?;
-        let later_page = later_page.parse()?;
+impl OrderingRules {
+    fn parse(section: &Section) -> Result<Self, ParseError> {
+        let mut later_pages: HashMap<Page, HashSet<Page>> = HashMap::new();
+        let mut rule_lines: HashMap<Rule, Vec<usize>> = HashMap::new();
 
-        ordering_rules
-            .entry(Page(earlier_page))
-            .or_default()
-            .insert(Page(later_page));
+        for ordering_rule in section.lines() {
+            let (earlier_page, later_page) = ordering_rule
+                .split_once('|')
+                .ok_or_else(|| ordering_rule.error("Ordering rule does not have a separator."))?;
+
+            let earlier_page = ordering_rule.parse_part(earlier_page)?;
+            let later_page = ordering_rule.parse_part(later_page)?;
+
+            later_pages
+                .entry(Page(earlier_page))
+                .or_default()
+                .insert(Page(later_page));
+
+            rule_lines
+                .entry((Page(earlier_page), Page(later_page)))
+                .or_default()
+                .push(ordering_rule.number());
+        }
+
+        Ok(Self {
+            later_pages,
+            rule_lines,
+        })
     }
 
-    let ordering_rules = ordering_rules;
+    fn contains(&self, (earlier_page, later_page): Rule) -> bool {
+        self.later_pages
+            .get(&earlier_page)
+            .is_some_and(|later_pages| later_pages.contains(&later_page))
+    }
 
-    let mut middle_page_sum = 0;
+    /// The rules broken by an update, in the order of the pages they require to come first.
+    fn broken_rules(&self, pages: &[Page]) -> Vec<Rule> {
+        let mut broken_rules = Vec::new();
+
+        for (index, &page) in pages.iter().enumerate() {
+            for &earlier_page in &pages[..index] {
+                if self.contains((page, earlier_page)) {
+                    broken_rules.push((page, earlier_page));
+                }
+            }
+        }
+
+        broken_rules
+    }
+
+    /// The number of pairs of pages in an update whose relative order is decided by a rule.
+    fn covered_pair_count(&self, pages: &[Page]) -> usize {
+        pages
+            .iter()
+            .enumerate()
+            .flat_map(|(index, &page)| {
+                pages[index + 1..]
+                    .iter()
+                    .map(move |&later_page| (page, later_page))
+            })
+            .filter(|&(page, later_page)| {
+                self.contains((page, later_page)) || self.contains((later_page, page))
+            })
+            .count()
+    }
 
-    'updates: for update in updates.lines() {
-        let pages: anyhow::Result<Vec<Page>> = update
-            .split(',')
-            .map(|page| Ok(Page(page.parse()?)))
+    /// Rules which are redundant, because they appear more than once, or contradictory, because
+    /// they require a page to come before itself or the reverse rule also appears, along with a
+    /// description of the problem.
+    fn problems(&self) -> Vec<(Rule, String)> {
+        let mut problems: Vec<(Rule, String)> = self
+            .rule_lines
+            .iter()
+            .filter_map(|(&(earlier_page, later_page), lines)| {
+                let problem = if earlier_page == later_page {
+                    String::from("contradictory: page must come before itself")
+                } else if let Some(reverse_lines) = self.rule_lines.get(&(later_page, earlier_page))
+                {
+                    format!(
+                        "contradictory: reversed on line {}",
+                        join(reverse_lines.iter())
+                    )
+                } else if lines.len() > 1 {
+                    String::from("redundant: duplicated")
+                } else {
+                    return None;
+                };
+
+                Some(((earlier_page, later_page), problem))
+            })
             .collect();
-        let pages = pages?;
 
-        if pages.len() % 2 == 0 {
-            bail!("The number of page numbers for a given update should be odd.");
-        }
+        problems.sort_unstable_by_key(|&(rule, _)| self.rule_lines[&rule][0]);
 
+        problems
+    }
+
+    fn is_correctly_ordered(&self, pages: &[Page]) -> bool {
         let mut seen_pages: HashSet<Page> = HashSet::new();
 
-        for &page in &pages {
-            if ordering_rules
+        pages.iter().all(|&page| {
+            let is_correctly_ordered = self
+                .later_pages
                 .get(&page)
-                .is_some_and(|following_pages| !following_pages.is_disjoint(&seen_pages))
-            {
-                continue 'updates;
-            }
+                .is_none_or(|later_pages| later_pages.is_disjoint(&seen_pages));
 
             seen_pages.insert(page);
+
+            is_correctly_ordered
+        })
+    }
+
+    /// The rules which apply to an update, as a graph with an edge from each page to every page
+    /// which must come after it.
+    fn graph(&self, pages: &[Page]) -> DiGraphMap<Page, ()> {
+        let page_set: HashSet<Page> = pages.iter().copied().collect();
+
+        let mut graph = DiGraphMap::with_capacity(pages.len(), 0);
+
+        for &page in pages {
+            graph.add_node(page);
+
+            for &later_page in self.later_pages.get(&page).into_iter().flatten() {
+                if page_set.contains(&later_page) {
+                    graph.add_edge(page, later_page, ());
+                }
+            }
         }
 
-        let Page(middle_page) = pages[pages.len() / 2];
-        middle_page_sum += middle_page;
+        graph
     }
 
-    Ok(middle_page_sum)
+    /// Orders an update's pages so that every rule which applies to it is satisfied.
+    fn corrected_order(&self, pages: &[Page]) -> Result<Vec<Page>, PageCycle> {
+        let graph = self.graph(pages);
+
+        toposort(&graph, None).map_err(|_| {
+            // The sort only tells us one page on a cycle, so find every page which is part of
+            // one, listed in the update's order.
+            let mut cycle_pages: Vec<Page> = tarjan_scc(&graph)
+                .into_iter()
+                .find(|component| {
+                    component.len() > 1 || graph.contains_edge(component[0], component[0])
+                })
+                .unwrap_or_default();
+
+            cycle_pages.sort_by_key(|cycle_page| pages.iter().position(|page| page == cycle_page));
+
+            PageCycle(cycle_pages)
+        })
+    }
 }
 
-fn part2(input: &str) -> anyhow::Result<u64> {
-    let (ordering_rules_raw, updates) = input
-        .split_once("\n\n")
-        .ok_or(anyhow!("Input is not comprised of 2 sections."))?;
+/// The ordering rules as a dense matrix of bits, where bit `later_page` of row `earlier_page` is
+/// set if there's a rule `earlier_page|later_page`.
+struct RuleBitset {
+    bits: Vec<u64>,
+    words_per_row: usize,
+}
+
+impl RuleBitset {
+    /// The largest page number for which a bitset is built, which keeps it to 2MiB at most.
+    const MAX_PAGE: u64 = (1 << 12) - 1;
+
+    fn new(ordering_rules: &OrderingRules) -> Option<Self> {
+        let max_page = ordering_rules
+            .rule_lines
+            .keys()
+            .map(|&(Page(earlier_page), Page(later_page))| earlier_page.max(later_page))
+            .max()
+            .unwrap_or_default();
+
+        if max_page > Self::MAX_PAGE {
+            return None;
+        }
 
-    let mut ordering_rules: HashMap<Page, HashSet<Page>> = HashMap::new();
+        let page_count = max_page as usize + 1;
+        let words_per_row = page_count.div_ceil(64);
 
-    for ordering_rule in ordering_rules_raw.lines() {
-        let (earlier_page, later_page) = ordering_rule
-            .split_once('|')
-            .ok_or(anyhow!("Ordering rule does not have a separator."))?;
+        let mut bits = vec![0; page_count * words_per_row];
 
-        let earlier_page = earlier_page.parse()?;
-        let later_page = later_page.parse()?;
+        for &(Page(earlier_page), Page(later_page)) in ordering_rules.rule_lines.keys() {
+            let (earlier_page, later_page) = (earlier_page as usize, later_page as usize);
 
-        ordering_rules
-            .entry(Page(earlier_page))
-            .or_default()
-            .insert(Page(later_page));
+            bits[earlier_page * words_per_row + later_page / 64] |= 1 << (later_page % 64);
+        }
+
+        Some(Self {
+            bits,
+            words_per_row,
+        })
     }
 
-    let ordering_rules = ordering_rules;
+    fn contains(&self, (Page(earlier_page), Page(later_page)): Rule) -> bool {
+        if earlier_page.max(later_page) > Self::MAX_PAGE {
+            return false;
+        }
 
-    let mut middle_page_sum = 0;
+        let (earlier_page, later_page) = (earlier_page as usize, later_page as usize);
 
-    for update in updates.lines() {
-        let pages: anyhow::Result<Vec<Page>> = update
-            .split(',')
-            .map(|page| Ok(Page(page.parse()?)))
-            .collect();
-        let pages = pages?;
+        self.bits
+            .get(earlier_page * self.words_per_row + later_page / 64)
+            .is_some_and(|word| word & (1 << (later_page % 64)) != 0)
+    }
 
-        if pages.len() % 2 == 0 {
-            bail!("The number of page numbers for a given update should be odd.");
+    /// Finds the page which would be in the middle of an update once it's correctly ordered,
+    /// without ordering it, by counting the pages which must come before each page.
+    ///
+    /// The middle page is the one with exactly half of the other pages before it, but this is only
+    /// well defined if the rules decide the order of every pair of pages in the update one way,
+    /// and every page has a different number of pages before it. Otherwise, returns `None`.
+    fn middle_page(&self, pages: &[Page]) -> Option<Page> {
+        let mut predecessor_counts = vec![0; pages.len()];
+
+        for (index, &page) in pages.iter().enumerate() {
+            for (later_index, &later_page) in pages.iter().enumerate().skip(index + 1) {
+                match (
+                    self.contains((page, later_page)),
+                    self.contains((later_page, page)),
+                ) {
+                    (true, false) => predecessor_counts[later_index] += 1,
+                    (false, true) => predecessor_counts[index] += 1,
+                    _ => return None,
+                }
+            }
         }
 
-        let mut seen_pages: HashSet<Page> = HashSet::new();
+        let mut seen_counts = vec![false; pages.len()];
 
-        let page_set: HashSet<Page> = pages.iter().copied().collect();
+        for &predecessor_count in &predecessor_counts {
+            if std::mem::replace(&mut seen_counts[predecessor_count], true) {
+                return None;
+            }
+        }
 
-        let mut ordering_rules: HashMap<Page, HashSet<Page>> = ordering_rules
+        let middle_index = predecessor_counts
             .iter()
-            .filter(|(earlier_page, _)| page_set.contains(earlier_page))
-            .filter_map(|(&earlier_page, later_pages)| {
-                let relevant_pages: HashSet<Page> =
-                    page_set.intersection(later_pages).copied().collect();
+            .position(|&predecessor_count| predecessor_count == pages.len() / 2)?;
 
-                if relevant_pages.is_empty() {
-                    None
-                } else {
-                    Some((earlier_page, relevant_pages))
-                }
-            })
-            .collect();
+        Some(pages[middle_index])
+    }
+}
 
-        let mut incorrectly_ordered_update = false;
+struct Update<'a> {
+    line: Line<'a>,
+    pages: Vec<Page>,
+}
 
-        for &page in &pages {
-            if ordering_rules
-                .get(&page)
-                .is_some_and(|following_pages| !following_pages.is_disjoint(&seen_pages))
-            {
-                incorrectly_ordered_update = true;
-                break;
-            }
+impl Update<'_> {
+    fn middle_page(pages: &[Page]) -> Page {
+        pages[pages.len() / 2]
+    }
 
-            seen_pages.insert(page);
+    /// The middle page of this update once it's correctly ordered, found by fully ordering it.
+    fn corrected_middle_page(&self, ordering_rules: &OrderingRules) -> Result<Page, ParseError> {
+        let corrected_pages = ordering_rules
+            .corrected_order(&self.pages)
+            .map_err(|cycle| self.line.error(cycle))?;
+
+        Ok(Self::middle_page(&corrected_pages))
+    }
+
+    /// As [`Update::corrected_middle_page`], but using [`RuleBitset::middle_page`] where possible.
+    fn fast_corrected_middle_page(
+        &self,
+        ordering_rules: &OrderingRules,
+        rule_bitset: Option<&RuleBitset>,
+    ) -> Result<Page, ParseError> {
+        match rule_bitset.and_then(|rule_bitset| rule_bitset.middle_page(&self.pages)) {
+            Some(middle_page) => Ok(middle_page),
+            None => self.corrected_middle_page(ordering_rules),
         }
+    }
+}
+
+fn parse_input(input: &str) -> anyhow::Result<(OrderingRules, Vec<Update<'_>>)> {
+    let [ordering_rules, updates] = parse::split_sections(input)?;
+
+    let ordering_rules = OrderingRules::parse(&ordering_rules)?;
+
+    let updates = updates
+        .lines()
+        .map(|line| {
+            let pages: Vec<Page> = line.parse_split(',')?.into_iter().map(Page).collect();
+
+            if pages.len().is_multiple_of(2) {
+                return Err(
+                    line.error("The number of page numbers for a given update should be odd.")
+                );
+            }
+
+            Ok(Update { line, pages })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok((ordering_rules, updates))
+}
+
+fn part1(input: &str) -> anyhow::Result<u64> {
+    let (ordering_rules, updates) = parse_input(input)?;
+
+    let middle_page_sum = updates
+        .iter()
+        .filter(|update| ordering_rules.is_correctly_ordered(&update.pages))
+        .map(|update| Update::middle_page(&update.pages).0)
+        .sum();
+
+    Ok(middle_page_sum)
+}
+
+fn part2(input: &str) -> anyhow::Result<u64> {
+    let (ordering_rules, updates) = parse_input(input)?;
+
+    let rule_bitset = RuleBitset::new(&ordering_rules);
+
+    let mut middle_page_sum = 0;
 
-        if !incorrectly_ordered_update {
+    for update in updates {
+        if ordering_rules.is_correctly_ordered(&update.pages) {
             continue;
         }
 
-        let mut reverse_ordered_pages: Vec<Page> = Vec::with_capacity(pages.len());
+        let Page(middle_page) =
+            update.fast_corrected_middle_page(&ordering_rules, rule_bitset.as_ref())?;
 
-        let mut unprocessed_pages = VecDeque::from(pages.clone());
+        middle_page_sum += middle_page;
+    }
 
-        while let Some(page) = unprocessed_pages.pop_front() {
-            if !ordering_rules.contains_key(&page) {
-                reverse_ordered_pages.push(page);
+    Ok(middle_page_sum)
+}
 
-                let mut rules_to_remove = Vec::new();
+/// Times how long `solve` takes to find the sum of the corrected middle pages of some updates,
+/// averaged over several runs.
+fn time_solver(mut solve: impl FnMut() -> u64) -> (u64, Duration) {
+    const RUN_COUNT: u32 = 20;
 
-                for (&earlier_page, later_pages) in ordering_rules.iter_mut() {
-                    later_pages.remove(&page);
+    let mut middle_page_sum = 0;
+    let start = Instant::now();
+
+    for _ in 0..RUN_COUNT {
+        middle_page_sum = hint::black_box(solve());
+    }
+
+    (middle_page_sum, start.elapsed() / RUN_COUNT)
+}
 
-                    if later_pages.is_empty() {
-                        rules_to_remove.push(earlier_page);
-                    }
+fn join(items: impl Iterator<Item = impl ToString>) -> String {
+    items
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Lists the corrected ordering of each update, or the pages preventing it from being ordered,
+/// which rules each update breaks and how much of it the rules cover, and any problems with the
+/// rules themselves.
+fn diagnose(input: &str) -> anyhow::Result<Vec<Table>> {
+    let (ordering_rules, updates) = parse_input(input)?;
+
+    let mut table = Table::new(
+        "Update orderings",
+        ["Line", "Pages", "Status", "Corrected order", "Middle page"],
+    );
+
+    let join_pages = |pages: &[Page]| join(pages.iter());
+
+    for update in &updates {
+        let (status, corrected_pages) = if ordering_rules.is_correctly_ordered(&update.pages) {
+            (String::from("correct"), Some(update.pages.clone()))
+        } else {
+            match ordering_rules.corrected_order(&update.pages) {
+                Ok(corrected_pages) => (String::from("reordered"), Some(corrected_pages)),
+                Err(PageCycle(cycle_pages)) => {
+                    (format!("cycle: {}", join_pages(&cycle_pages)), None)
                 }
+            }
+        };
+
+        table.push_row([
+            update.line.number().to_string(),
+            join_pages(&update.pages),
+            status,
+            corrected_pages
+                .as_deref()
+                .map(join_pages)
+                .unwrap_or_default(),
+            corrected_pages
+                .as_deref()
+                .map(|pages| Update::middle_page(pages).to_string())
+                .unwrap_or_default(),
+        ]);
+    }
+
+    let mut coverage_table = Table::new(
+        "Rule coverage per update",
+        [
+            "Line",
+            "Page pairs",
+            "Covered",
+            "Coverage",
+            "Broken",
+            "Broken rules",
+        ],
+    );
+
+    for update in &updates {
+        let page_count = update.pages.len();
+        let pair_count = page_count * page_count.saturating_sub(1) / 2;
+        let covered_pair_count = ordering_rules.covered_pair_count(&update.pages);
+
+        let coverage = if pair_count == 0 {
+            String::from("-")
+        } else {
+            format!(
+                "{:.0}%",
+                100.0 * covered_pair_count as f64 / pair_count as f64
+            )
+        };
+
+        let broken_rules: Vec<String> = ordering_rules
+            .broken_rules(&update.pages)
+            .into_iter()
+            .map(|(earlier_page, later_page)| format!("{earlier_page}|{later_page}"))
+            .collect();
+
+        coverage_table.push_row([
+            update.line.number().to_string(),
+            pair_count.to_string(),
+            covered_pair_count.to_string(),
+            coverage,
+            broken_rules.len().to_string(),
+            broken_rules.join(" "),
+        ]);
+    }
+
+    let mut problem_table = Table::new("Rule problems", ["Rule", "Lines", "Problem"]);
+
+    let problems = ordering_rules.problems();
+    let problem_count = problems.len();
+
+    for ((earlier_page, later_page), problem) in problems {
+        problem_table.push_row([
+            format!("{earlier_page}|{later_page}"),
+            join(ordering_rules.rule_lines[&(earlier_page, later_page)].iter()),
+            problem,
+        ]);
+    }
+
+    problem_table.set_footer([
+        String::from("Total"),
+        String::new(),
+        problem_count.to_string(),
+    ]);
+
+    // Updates whose pages form a cycle can't be corrected, so they're left out of the timings.
+    let (incorrectly_ordered_updates, cyclic_updates): (Vec<&Update>, Vec<&Update>) = updates
+        .iter()
+        .filter(|update| !ordering_rules.is_correctly_ordered(&update.pages))
+        .partition(|update| ordering_rules.corrected_order(&update.pages).is_ok());
+
+    let mut timing_table = Table::new(
+        "Part 2 middle page solvers",
+        ["Solver", "Middle page sum", "Time per run"],
+    );
+
+    let (middle_page_sum, duration) = time_solver(|| {
+        incorrectly_ordered_updates
+            .iter()
+            .filter_map(|update| update.corrected_middle_page(&ordering_rules).ok())
+            .map(|middle_page| middle_page.0)
+            .sum()
+    });
+
+    timing_table.push_row([
+        String::from("topological sort"),
+        middle_page_sum.to_string(),
+        format!("{duration:?}"),
+    ]);
+
+    let (middle_page_sum, duration) = time_solver(|| {
+        // Building the bitset is part of the cost of this approach, so it's included in each run.
+        let rule_bitset = RuleBitset::new(&ordering_rules);
+
+        incorrectly_ordered_updates
+            .iter()
+            .filter_map(|update| {
+                rule_bitset
+                    .as_ref()
+                    .and_then(|rule_bitset| rule_bitset.middle_page(&update.pages))
+                    .or_else(|| update.corrected_middle_page(&ordering_rules).ok())
+            })
+            .map(|middle_page| middle_page.0)
+            .sum()
+    });
+
+    timing_table.push_row([
+        String::from("predecessor counting"),
+        middle_page_sum.to_string(),
+        format!("{duration:?}"),
+    ]);
+
+    if !cyclic_updates.is_empty() {
+        timing_table.set_footer([
+            String::from("Skipped cyclic updates"),
+            cyclic_updates.len().to_string(),
+            String::new(),
+        ]);
+    }
+
+    Ok(vec![table, coverage_table, problem_table, timing_table])
+}
 
-                for page in rules_to_remove {
-                    ordering_rules.remove(&page);
+#[cfg(test)]
+mod tests {
+    use fastrand::Rng;
+
+    use super::*;
+
+    /// Random rules which mostly follow one order of the pages, with some pairs left unordered
+    /// and some rules reversed, followed by random updates of those pages.
+    fn random_input(rng: &mut Rng) -> String {
+        let mut pages: Vec<u64> = (10..30).collect();
+        rng.shuffle(&mut pages);
+
+        let mut input = String::new();
+
+        for (index, &earlier_page) in pages.iter().enumerate() {
+            for &later_page in &pages[index + 1..] {
+                match rng.u32(0..100) {
+                    0..5 => {}
+                    5..7 => input.push_str(&format!("{later_page}|{earlier_page}\n")),
+                    _ => input.push_str(&format!("{earlier_page}|{later_page}\n")),
                 }
-            } else {
-                unprocessed_pages.push_back(page);
             }
         }
 
-        let Page(middle_page) = reverse_ordered_pages[reverse_ordered_pages.len() / 2];
-        middle_page_sum += middle_page;
+        input.push('\n');
+
+        for _ in 0..20 {
+            let mut update = pages.clone();
+            rng.shuffle(&mut update);
+
+            let update: Vec<String> = update[..2 * rng.usize(0..5) + 1]
+                .iter()
+                .map(u64::to_string)
+                .collect();
+
+            input.push_str(&update.join(","));
+            input.push('\n');
+        }
+
+        input
     }
 
-    Ok(middle_page_sum)
+    #[test]
+    fn rule_bitset_middle_page_matches_sorting() {
+        let mut rng = Rng::with_seed(5);
+        let mut checked_count = 0;
+
+        for _ in 0..200 {
+            let input = random_input(&mut rng);
+            let (ordering_rules, updates) = parse_input(&input).unwrap();
+            let rule_bitset = RuleBitset::new(&ordering_rules).unwrap();
+
+            for update in updates {
+                let Some(middle_page) = rule_bitset.middle_page(&update.pages) else {
+                    continue;
+                };
+
+                assert_eq!(
+                    Some(middle_page),
+                    update.corrected_middle_page(&ordering_rules).ok(),
+                    "Update {:?}",
+                    update.pages
+                );
+
+                checked_count += 1;
+            }
+        }
+
+        assert!(
+            checked_count > 0,
+            "No update had a well defined middle page."
+        );
+    }
+
+    #[test]
+    fn diagnose_skips_cyclic_updates() {
+        let tables = diagnose("1|2\n2|3\n3|1\n\n1,2,3\n3,2,1,4,5").unwrap();
+
+        assert_eq!(tables.len(), 4);
+    }
 }
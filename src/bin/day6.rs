@@ -1,22 +1,32 @@
-use std::{
-    cell::{Cell, OnceCell},
-    cmp::Ordering,
-    collections::{BTreeSet, HashMap, HashSet},
-    ops::{Bound, Index},
+use std::{collections::HashSet, env, fmt, fs, iter, str::FromStr, thread, time::Duration};
+
+use adventofcode::{
+    diagnosing_single_input, env_choice,
+    grid::{Color, Direction, Grid},
+    parse::{self, Line, ParseError},
+    report::Table,
+    solve_or_diagnose_day,
 };
-
-use adventofcode::solve_day;
-use anyhow::anyhow;
+use anyhow::{anyhow, bail, ensure};
 use glam::IVec2;
 
 fn main() -> anyhow::Result<()> {
-    solve_day(file!(), part1, part2)
+    solve_or_diagnose_day(file!(), part1, part2, diagnose)
+}
+
+/// Where a guard starts their patrol, and the direction they start off facing.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Guard {
+    direction: Direction,
+    start_position: IVec2,
 }
 
+/// A map of the lab, and the guards patrolling it. The puzzle has a single guard, but any number
+/// are accepted, each patrolling independently of the others.
 struct Lab {
-    col_count: i32,
-    rows: Vec<Vec<PositionType>>,
-    row_count: i32,
+    /// The lab's guards, in reading order of their start positions.
+    guards: Vec<Guard>,
+    map: Grid<PositionType>,
 }
 
 #[derive(Eq, PartialEq)]
@@ -25,70 +35,387 @@ enum PositionType {
     Obstruction,
 }
 
-impl Index<usize> for Lab {
-    type Output = Vec<PositionType>;
+impl FromStr for Lab {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut guards = Vec::new();
+
+        let map = parse::grid(parse::lines(s), |position, cell| {
+            if let Some(direction) = guard_direction(cell) {
+                guards.push(Guard {
+                    direction,
+                    start_position: position,
+                });
+
+                return Ok(PositionType::Empty);
+            }
+
+            PositionType::try_from(cell)
+        })?;
+
+        if guards.is_empty() {
+            bail!("No guard found when parsing input!");
+        }
 
-    fn index(&self, index: usize) -> &Self::Output {
-        self.rows.index(index)
+        Ok(Self { guards, map })
     }
 }
 
-impl<I2: IntoIterator<Item = PositionType>> FromIterator<I2> for Lab {
-    fn from_iter<I1>(iter: I1) -> Self
-    where
-        I1: IntoIterator<Item = I2>,
-    {
-        let rows: Vec<Vec<PositionType>> = iter
+impl Lab {
+    /// Environment variable which, when set to the path of a patrol trace as written by
+    /// [`diagnose`] with [`Diagnostics::Trace`], replays the trace in the terminal over the lab.
+    /// Only allowed when a single input is selected with `--initials`.
+    const REPLAY_VAR: &str = "AOC_DAY6_REPLAY";
+
+    /// Environment variable setting the number of milliseconds between each step of a replay.
+    const REPLAY_DELAY_VAR: &str = "AOC_DAY6_REPLAY_DELAY";
+
+    const DEFAULT_REPLAY_DELAY: Duration = Duration::from_millis(100);
+
+    /// The lab's only guard, as the puzzle's answers are only defined for a single guard.
+    fn single_guard(&self) -> anyhow::Result<Guard> {
+        match self.guards[..] {
+            [guard] => Ok(guard),
+            _ => bail!(
+                "Found {} guards, but the puzzle only allows one!",
+                self.guards.len()
+            ),
+        }
+    }
+
+    fn patrol(&self, guard: Guard) -> Patrol<'_> {
+        Patrol {
+            direction: guard.direction,
+            extra_obstruction: None,
+            finished: false,
+            lab: self,
+            position: guard.start_position,
+            seen_turns: HashSet::new(),
+        }
+    }
+
+    fn visited_positions(&self, guard: Guard) -> HashSet<IVec2> {
+        iter::once(guard.start_position)
+            .chain(
+                self.patrol(guard)
+                    .filter(|step| step.event == Event::Move)
+                    .map(|step| step.position),
+            )
+            .collect()
+    }
+
+    /// Finds every position where adding an obstruction would trap the guard in a loop, in
+    /// reading order, along with the turns which make up each loop.
+    ///
+    /// This simulates the whole patrol for every candidate, so is much slower than [`part2`], but
+    /// doesn't share any of its shortcuts, which makes it useful for checking it.
+    fn loop_obstructions(&self, guard: Guard) -> Vec<(IVec2, Vec<Step>)> {
+        let mut candidate_positions: Vec<IVec2> = self
+            .visited_positions(guard)
             .into_iter()
-            .map(|char_iter| char_iter.into_iter().collect())
+            .filter(|&position| position != guard.start_position)
             .collect();
 
-        assert!(!rows.is_empty(), "No rows in Lab.");
+        candidate_positions.sort_unstable_by_key(|position| (position.y, position.x));
 
-        let row_count = rows.len();
-        let col_count = rows[0].len();
+        candidate_positions
+            .into_iter()
+            .filter_map(|candidate_position| {
+                let turns: Vec<Step> = self
+                    .patrol(guard)
+                    .with_obstruction(candidate_position)
+                    .filter(|step| step.event != Event::Move)
+                    .collect();
+
+                let &Step {
+                    direction,
+                    event: Event::LoopDetected,
+                    position,
+                } = turns.last()?
+                else {
+                    return None;
+                };
+
+                // The loop starts from the first time the guard turned where they were found to
+                // be looping.
+                let loop_start = turns
+                    .iter()
+                    .position(|turn| turn.position == position && turn.direction == direction)?;
+
+                Some((
+                    candidate_position,
+                    turns[loop_start..turns.len() - 1].to_vec(),
+                ))
+            })
+            .collect()
+    }
 
-        assert!(
-            rows[1..].iter().all(|row| row.len() == col_count),
-            "Lab rows are not all of equal length."
+    /// Animates the patrol trace in the file named by [`Lab::REPLAY_VAR`] on stderr, if it's set.
+    fn replay(&self) -> anyhow::Result<()> {
+        let Ok(path) = env::var(Self::REPLAY_VAR) else {
+            return Ok(());
+        };
+
+        // A trace only matches the lab it was recorded in, so it can't be replayed over every
+        // participant's input.
+        ensure!(
+            diagnosing_single_input(),
+            "`{}` requires `--initials` to select a single input.",
+            Self::REPLAY_VAR
         );
 
-        Self {
-            col_count: col_count as i32,
-            rows,
-            row_count: row_count as i32,
+        let delay = match env::var(Self::REPLAY_DELAY_VAR) {
+            Ok(delay) => Duration::from_millis(delay.trim().parse()?),
+            Err(_) => Self::DEFAULT_REPLAY_DELAY,
+        };
+
+        let trace = parse::normalize(&fs::read_to_string(&path)?);
+
+        let mut current_guard_index = None;
+        let mut visited_positions = HashSet::new();
+
+        for trace_step in parse_trace(&trace)? {
+            let TraceStep {
+                guard_index,
+                step,
+                step_index,
+            } = trace_step;
+
+            let guard = self.guards.get(guard_index).ok_or(anyhow!(
+                "The trace has a guard {}, but the lab only has {}.",
+                guard_index + 1,
+                self.guards.len()
+            ))?;
+
+            ensure!(
+                self.map.in_bounds(step.position),
+                "Step {} of guard {}'s trace is outside the lab.",
+                step_index + 1,
+                guard_index + 1
+            );
+
+            if current_guard_index != Some(guard_index) {
+                current_guard_index = Some(guard_index);
+                visited_positions = HashSet::from([guard.start_position]);
+            }
+
+            visited_positions.insert(step.position);
+
+            let frame = self
+                .map
+                .render(PositionType::char)
+                .background(Color::Dim)
+                .highlight(visited_positions.iter().copied(), Color::Cyan)
+                .mark_colored([step.position], guard_char(step.direction), Color::Yellow);
+
+            // Clear the terminal before drawing each frame over the top of the last.
+            eprintln!("\x1b[H\x1b[2J{frame}");
+            eprintln!(
+                "Guard {}, step {}: {:?} at {}, {}",
+                guard_index + 1,
+                step_index + 1,
+                step.event,
+                step.position.x,
+                step.position.y
+            );
+
+            thread::sleep(delay);
         }
+
+        Ok(())
     }
 }
 
-impl Lab {
-    fn in_bounds(&self, position: IVec2) -> bool {
-        position.min_element() >= 0 && position.x < self.col_count && position.y < self.row_count
+/// What happened on a single step of the guard's patrol.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Event {
+    /// The guard moved forward to a new position.
+    Move,
+    /// The guard turned right to avoid an obstruction.
+    Turn,
+    /// The guard walked out of the lab.
+    Exit,
+    /// The guard turned right at a position and direction they'd already turned at, so will keep
+    /// walking the same loop forever.
+    LoopDetected,
+}
+
+impl Event {
+    const ALL: [Self; 4] = [Self::Move, Self::Turn, Self::Exit, Self::LoopDetected];
+}
+
+/// A step of the guard's patrol, with the guard's position and direction after the step.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Step {
+    direction: Direction,
+    event: Event,
+    position: IVec2,
+}
+
+/// A step read back from a patrol trace, along with which guard took it.
+struct TraceStep {
+    /// The 0-based index of the guard in [`Lab::guards`].
+    guard_index: usize,
+    step: Step,
+    /// The 0-based index of the step within the guard's patrol.
+    step_index: usize,
+}
+
+/// The columns of a patrol trace, as written by [`trace_table`] and read by [`parse_trace`].
+const TRACE_HEADERS: [&str; 6] = ["Guard", "Step", "X", "Y", "Direction", "Event"];
+
+/// Lists every step of each guard's patrol.
+fn trace_table(lab: &Lab) -> Table {
+    let mut table = Table::new("Patrol trace", TRACE_HEADERS);
+
+    for (guard_index, &guard) in lab.guards.iter().enumerate() {
+        for (step_index, step) in lab.patrol(guard).enumerate() {
+            table.push_row([
+                (guard_index + 1).to_string(),
+                (step_index + 1).to_string(),
+                step.position.x.to_string(),
+                step.position.y.to_string(),
+                format!("{:?}", step.direction),
+                format!("{:?}", step.event),
+            ]);
+        }
     }
 
-    fn visited_positions(&self, start_position: IVec2) -> HashSet<IVec2> {
-        let mut current_position = start_position;
-        let mut direction = Direction::North;
+    table
+}
 
-        let mut visited_positions: HashSet<IVec2> = HashSet::new();
+/// Reads a patrol trace written as CSV by [`trace_table`].
+fn parse_trace(trace: &str) -> Result<Vec<TraceStep>, ParseError> {
+    let mut lines = parse::lines(trace);
 
-        loop {
-            visited_positions.insert(current_position);
+    let Some(header) = lines.next() else {
+        return Ok(Vec::new());
+    };
 
-            let next_position = current_position + direction.xy();
+    if header.text() != TRACE_HEADERS.join(",") {
+        return Err(header.error(format!(
+            "A patrol trace should start with the header `{}`.",
+            TRACE_HEADERS.join(",")
+        )));
+    }
 
-            if !self.in_bounds(next_position) {
-                break visited_positions;
+    lines
+        .map(|line| {
+            let [guard, step, x, y, direction, event] = line
+                .split(',')
+                .collect::<Vec<_>>()
+                .try_into()
+                .map_err(|_| {
+                    line.error(format!(
+                        "A trace step should have {} fields.",
+                        TRACE_HEADERS.len()
+                    ))
+                })?;
+
+            let guard_number: usize = line.parse_part(guard)?;
+            let step_number: usize = line.parse_part(step)?;
+
+            if guard_number == 0 || step_number == 0 {
+                return Err(line.error("Guards and steps are numbered from 1."));
             }
 
-            match self[next_position.y as usize][next_position.x as usize] {
-                PositionType::Empty => {
-                    current_position = next_position;
-                }
-                PositionType::Obstruction => {
-                    direction = direction.next();
+            Ok(TraceStep {
+                guard_index: guard_number - 1,
+                step: Step {
+                    direction: parse_name(line, direction, &Direction::ALL)?,
+                    event: parse_name(line, event, &Event::ALL)?,
+                    position: IVec2::new(line.parse_part(x)?, line.parse_part(y)?),
+                },
+                step_index: step_number - 1,
+            })
+        })
+        .collect()
+}
+
+/// Parses `part` as whichever of `values` it names, as written by their `Debug` impl.
+fn parse_name<T: Copy + fmt::Debug>(
+    line: Line<'_>,
+    part: &str,
+    values: &[T],
+) -> Result<T, ParseError> {
+    values
+        .iter()
+        .copied()
+        .find(|value| format!("{value:?}") == part.trim())
+        .ok_or_else(|| line.error_in(part, format!("Unknown value `{}`.", part.trim())))
+}
+
+/// Simulates the guard's patrol one step at a time, until the guard leaves the lab or starts
+/// walking in a loop.
+struct Patrol<'a> {
+    direction: Direction,
+    /// An obstruction added to the lab for this patrol only.
+    extra_obstruction: Option<IVec2>,
+    finished: bool,
+    lab: &'a Lab,
+    position: IVec2,
+    /// Each position and new direction at which the guard has turned so far.
+    seen_turns: HashSet<(IVec2, Direction)>,
+}
+
+impl Patrol<'_> {
+    fn with_obstruction(mut self, position: IVec2) -> Self {
+        self.extra_obstruction = Some(position);
+        self
+    }
+}
+
+impl Iterator for Patrol<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let next_position = self.position + self.direction.xy();
+
+        let position_type = if self.extra_obstruction == Some(next_position) {
+            Some(&PositionType::Obstruction)
+        } else {
+            self.lab.map.get(next_position)
+        };
+
+        let event = match position_type {
+            None => {
+                self.finished = true;
+                Event::Exit
+            }
+            Some(PositionType::Empty) => {
+                self.position = next_position;
+                Event::Move
+            }
+            Some(PositionType::Obstruction) => {
+                self.direction = self.direction.next();
+
+                if self.seen_turns.insert((self.position, self.direction)) {
+                    Event::Turn
+                } else {
+                    self.finished = true;
+                    Event::LoopDetected
                 }
             }
+        };
+
+        Some(Step {
+            direction: self.direction,
+            event,
+            position: self.position,
+        })
+    }
+}
+
+impl PositionType {
+    fn char(&self) -> char {
+        match self {
+            Self::Empty => '.',
+            Self::Obstruction => '#',
         }
     }
 }
@@ -98,291 +425,424 @@ impl TryFrom<char> for PositionType {
 
     fn try_from(value: char) -> Result<Self, Self::Error> {
         match value {
-            '.' | '^' => Ok(Self::Empty),
+            '.' => Ok(Self::Empty),
             '#' => Ok(Self::Obstruction),
             _ => Err(anyhow!(
-                "Maps should only be comprised of the characters `.`, `#` and `^`."
+                "Maps should only be comprised of the characters `.`, `#`, `^`, `>`, `v` and `<`."
             )),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-enum Direction {
-    East,
-    North,
-    South,
-    West,
+/// The direction a guard drawn as `char` is facing, if `char` is a guard.
+fn guard_direction(char: char) -> Option<Direction> {
+    Direction::ALL
+        .into_iter()
+        .find(|&direction| guard_char(direction) == char)
 }
 
-impl Direction {
-    const ALL: [Self; 4] = [Self::East, Self::North, Self::South, Self::West];
-
-    fn next(self) -> Self {
-        match self {
-            Self::East => Self::South,
-            Self::North => Self::East,
-            Self::South => Self::West,
-            Self::West => Self::North,
-        }
-    }
-
-    fn xy(self) -> IVec2 {
-        match self {
-            Self::East => IVec2::X,
-            Self::North => IVec2::NEG_Y,
-            Self::South => IVec2::Y,
-            Self::West => IVec2::NEG_X,
-        }
+/// The char used to draw the guard facing in `direction`.
+fn guard_char(direction: Direction) -> char {
+    match direction {
+        Direction::East => '>',
+        Direction::North => '^',
+        Direction::South => 'v',
+        Direction::West => '<',
     }
 }
 
 fn part1(input: &str) -> anyhow::Result<u64> {
-    let start_position = OnceCell::new();
-
-    let lab: Lab = input
-        .lines()
-        .enumerate()
-        .map(|(y, row)| {
-            let start_position = &start_position;
+    let lab: Lab = input.parse()?;
 
-            row.chars().enumerate().map(move |(x, cell)| {
-                if cell == '^' {
-                    start_position.set(IVec2::new(x as i32, y as i32)).unwrap();
-                }
-                PositionType::try_from(cell).unwrap()
-            })
-        })
-        .collect();
-
-    let visited_positions = lab.visited_positions(start_position.into_inner().unwrap());
+    let visited_positions = lab.visited_positions(lab.single_guard()?);
 
     Ok(visited_positions.len() as u64)
 }
 
-fn part2(input: &str) -> anyhow::Result<u64> {
-    let start_position = OnceCell::new();
+/// For every position and direction in the lab, the obstruction the guard would next walk into,
+/// if any, so that the guard can jump straight from one turn to the next.
+struct JumpTable {
+    next_obstructions: Vec<Option<IVec2>>,
+}
 
-    let lab: Lab = input
-        .lines()
-        .enumerate()
-        .map(|(y, row)| {
-            let start_position = &start_position;
+impl JumpTable {
+    fn new(map: &Grid<PositionType>) -> Self {
+        let mut next_obstructions = vec![None; state_count(map)];
 
-            row.chars().enumerate().map(move |(x, cell)| {
-                if cell == '^' {
-                    start_position.set(IVec2::new(x as i32, y as i32)).unwrap();
-                }
-                PositionType::try_from(cell).unwrap()
-            })
-        })
-        .collect();
+        for direction in Direction::ALL {
+            // Sweep backwards against the direction of travel, so that the last obstruction seen
+            // is the next one the guard would walk into.
+            let mut positions: Vec<IVec2> = map.positions().collect();
+
+            if matches!(direction, Direction::East | Direction::South) {
+                positions.reverse();
+            }
 
-    struct XFirstIVec2(IVec2);
+            // The next obstruction seen so far in each row or column, depending on the direction.
+            let mut line_next_obstructions: Vec<Option<IVec2>> =
+                vec![None; map.col_count().max(map.row_count()) as usize];
 
-    impl Eq for XFirstIVec2 {}
+            for position in positions {
+                let line = match direction {
+                    Direction::East | Direction::West => position.y,
+                    Direction::North | Direction::South => position.x,
+                } as usize;
 
-    impl Ord for XFirstIVec2 {
-        fn cmp(&self, other: &Self) -> Ordering {
-            match self.0.x.cmp(&other.0.x) {
-                ordering @ (Ordering::Less | Ordering::Greater) => ordering,
-                Ordering::Equal => self.0.y.cmp(&other.0.y),
+                next_obstructions[state_index(map, position, direction)] =
+                    line_next_obstructions[line];
+
+                if map[position] == PositionType::Obstruction {
+                    line_next_obstructions[line] = Some(position);
+                }
             }
         }
-    }
 
-    impl PartialEq for XFirstIVec2 {
-        fn eq(&self, other: &Self) -> bool {
-            self.0 == other.0
-        }
+        Self { next_obstructions }
     }
+}
 
-    impl PartialOrd for XFirstIVec2 {
-        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-            Some(self.cmp(other))
-        }
+/// The number of distinct (position, direction) states in the lab.
+fn state_count(map: &Grid<PositionType>) -> usize {
+    map.col_count() as usize * map.row_count() as usize * Direction::ALL.len()
+}
+
+/// A dense index for a (position, direction) state, for use in tables and bitmaps.
+fn state_index(map: &Grid<PositionType>, position: IVec2, direction: Direction) -> usize {
+    let direction_index = match direction {
+        Direction::East => 0,
+        Direction::North => 1,
+        Direction::South => 2,
+        Direction::West => 3,
+    };
+
+    (position.y as usize * map.col_count() as usize + position.x as usize) * Direction::ALL.len()
+        + direction_index
+}
+
+impl Lab {
+    /// Each position the guard visits, other than where they start, in the order they first
+    /// visit it, along with the guard's position and direction just before stepping onto it.
+    ///
+    /// Placing an obstruction at one of these positions doesn't change the guard's path up until
+    /// that point, so a simulation of the changed patrol can start from there.
+    fn first_visits(&self, guard: Guard) -> Vec<(IVec2, IVec2, Direction)> {
+        let mut visited_positions = HashSet::from([guard.start_position]);
+
+        self.patrol(guard)
+            .filter(|step| step.event == Event::Move && visited_positions.insert(step.position))
+            .map(|step| {
+                (
+                    step.position,
+                    step.position - step.direction.xy(),
+                    step.direction,
+                )
+            })
+            .collect()
     }
 
-    struct YFirstIVec2(IVec2);
+    /// Whether the guard walks in a loop, starting from `position` and `direction`, with an extra
+    /// obstruction at `new_obstruction`.
+    ///
+    /// `turns` is a bitmap of the (obstruction, direction) states the guard has turned at, which
+    /// should be clear beforehand and is left clear afterwards, so it can be reused between calls
+    /// without reallocating.
+    fn loops_with_obstruction(
+        &self,
+        jump_table: &JumpTable,
+        mut position: IVec2,
+        mut direction: Direction,
+        new_obstruction: IVec2,
+        turns: &mut [u64],
+    ) -> bool {
+        let mut set_turn_indices = Vec::new();
+
+        let is_loop = loop {
+            let mut next_obstruction =
+                jump_table.next_obstructions[state_index(&self.map, position, direction)];
+
+            // The new obstruction is hit first if it's ahead of the guard, and closer than the
+            // next existing obstruction.
+            let offset = new_obstruction - position;
+            let distance = offset.dot(direction.xy());
+
+            if distance > 0
+                && offset == direction.xy() * distance
+                && next_obstruction.is_none_or(|next_obstruction| {
+                    (next_obstruction - position).dot(direction.xy()) > distance
+                })
+            {
+                next_obstruction = Some(new_obstruction);
+            }
 
-    impl Eq for YFirstIVec2 {}
+            let Some(obstruction) = next_obstruction else {
+                break false;
+            };
 
-    impl Ord for YFirstIVec2 {
-        fn cmp(&self, other: &Self) -> Ordering {
-            match self.0.y.cmp(&other.0.y) {
-                ordering @ (Ordering::Less | Ordering::Greater) => ordering,
-                Ordering::Equal => self.0.x.cmp(&other.0.x),
+            let turn_index = state_index(&self.map, obstruction, direction);
+            let (word, bit) = (turn_index / 64, 1 << (turn_index % 64));
+
+            if turns[word] & bit != 0 {
+                break true;
             }
+
+            turns[word] |= bit;
+            set_turn_indices.push(turn_index);
+
+            position = obstruction - direction.xy();
+            direction = direction.next();
+        };
+
+        for turn_index in set_turn_indices {
+            turns[turn_index / 64] &= !(1 << (turn_index % 64));
         }
+
+        is_loop
     }
 
-    impl PartialEq for YFirstIVec2 {
-        fn eq(&self, other: &Self) -> bool {
-            self.0 == other.0
-        }
+    /// As [`Lab::loop_obstructions`], but only the positions, found using a [`JumpTable`] and
+    /// with the candidates split between threads.
+    fn fast_loop_obstructions(&self, guard: Guard) -> Vec<IVec2> {
+        let jump_table = JumpTable::new(&self.map);
+        let first_visits = self.first_visits(guard);
+
+        let thread_count = thread::available_parallelism().map_or(1, usize::from);
+        let chunk_size = first_visits.len().div_ceil(thread_count).max(1);
+
+        let mut loop_obstructions: Vec<IVec2> = thread::scope(|scope| {
+            let handles: Vec<_> = first_visits
+                .chunks(chunk_size)
+                .map(|first_visits| {
+                    let jump_table = &jump_table;
+
+                    scope.spawn(move || {
+                        let mut turns = vec![0; state_count(&self.map).div_ceil(64)];
+
+                        first_visits
+                            .iter()
+                            .filter(|&&(new_obstruction, position, direction)| {
+                                self.loops_with_obstruction(
+                                    jump_table,
+                                    position,
+                                    direction,
+                                    new_obstruction,
+                                    &mut turns,
+                                )
+                            })
+                            .map(|&(new_obstruction, ..)| new_obstruction)
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("Loop detection thread panicked."))
+                .collect()
+        });
+
+        loop_obstructions.sort_unstable_by_key(|position| (position.y, position.x));
+
+        loop_obstructions
     }
+}
 
-    impl PartialOrd for YFirstIVec2 {
-        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-            Some(self.cmp(other))
-        }
+fn part2(input: &str) -> anyhow::Result<u64> {
+    let lab: Lab = input.parse()?;
+
+    let guard = lab.single_guard()?;
+
+    let loop_obstructions = lab.fast_loop_obstructions(guard);
+
+    lab.map
+        .render(PositionType::char)
+        .mark_colored(loop_obstructions.iter().copied(), 'O', Color::Red)
+        .mark_colored(
+            [guard.start_position],
+            guard_char(guard.direction),
+            Color::Yellow,
+        )
+        .debug_print();
+
+    Ok(loop_obstructions.len() as u64)
+}
+
+/// Which tables [`diagnose`] produces.
+///
+/// Defaults to [`Diagnostics::All`], and can be overridden by setting [`Diagnostics::ENV_VAR`] to
+/// `all` or `trace`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Diagnostics {
+    /// A summary of each guard, the patrol trace, and every loop-causing obstruction.
+    All,
+    /// Only the patrol trace, so that `--csv` writes a trace file which can be replayed with
+    /// [`Lab::REPLAY_VAR`].
+    Trace,
+}
+
+impl Diagnostics {
+    const ENV_VAR: &str = "AOC_DAY6_DIAGNOSTICS";
+
+    fn from_env() -> anyhow::Result<Self> {
+        env_choice(
+            Self::ENV_VAR,
+            "Diagnostics",
+            &[("all", Self::All), ("trace", Self::Trace)],
+        )
     }
+}
 
-    let mut obstructions_by_col: BTreeSet<XFirstIVec2> = BTreeSet::new();
-    let mut obstructions_by_row: BTreeSet<YFirstIVec2> = BTreeSet::new();
+/// Summarises each guard's patrol, including which other guards' paths it crosses, lists every
+/// step of each patrol, and finds the obstructions which would trap each guard in a loop, or only
+/// lists the steps, depending on [`Diagnostics::ENV_VAR`]. Also replays a trace first, if
+/// [`Lab::REPLAY_VAR`] is set.
+fn diagnose(input: &str) -> anyhow::Result<Vec<Table>> {
+    let lab: Lab = input.parse()?;
 
-    for (y, row) in lab.rows.iter().enumerate() {
-        let y = y as i32;
-        for (x, cell) in row.iter().enumerate() {
-            let x = x as i32;
+    lab.replay()?;
 
-            if *cell == PositionType::Obstruction {
-                obstructions_by_col.insert(XFirstIVec2(IVec2 { x, y }));
-                obstructions_by_row.insert(YFirstIVec2(IVec2 { x, y }));
-            }
-        }
+    let trace_table = trace_table(&lab);
+
+    if Diagnostics::from_env()? == Diagnostics::Trace {
+        return Ok(vec![trace_table]);
     }
 
-    let mut next_obstructions: HashMap<(IVec2, Direction), Option<IVec2>> =
-        HashMap::with_capacity(4 * lab.row_count as usize * lab.col_count as usize);
-
-    for (y, row) in lab.rows.iter().enumerate() {
-        let y = y as i32;
-        for (x, cell) in row.iter().enumerate() {
-            let x = x as i32;
-
-            let position = IVec2 { x, y };
-
-            if *cell == PositionType::Empty {
-                for direction in Direction::ALL {
-                    let next_obstruction = match direction {
-                        Direction::East => obstructions_by_row
-                            .range((
-                                Bound::Excluded(YFirstIVec2(position)),
-                                Bound::Excluded(YFirstIVec2(IVec2 { x: 0, y: y + 1 })),
-                            ))
-                            .next()
-                            .map(|&YFirstIVec2(position)| position),
-                        Direction::North => obstructions_by_col
-                            .range((
-                                Bound::Excluded(XFirstIVec2(IVec2 {
-                                    x: x - 1,
-                                    y: lab.row_count - 1,
-                                })),
-                                Bound::Excluded(XFirstIVec2(position)),
-                            ))
-                            .last()
-                            .map(|&XFirstIVec2(position)| position),
-                        Direction::South => obstructions_by_col
-                            .range((
-                                Bound::Excluded(XFirstIVec2(position)),
-                                Bound::Excluded(XFirstIVec2(IVec2 { x: x + 1, y: 0 })),
-                            ))
-                            .next()
-                            .map(|&XFirstIVec2(position)| position),
-                        Direction::West => obstructions_by_row
-                            .range((
-                                Bound::Excluded(YFirstIVec2(IVec2 {
-                                    x: lab.col_count - 1,
-                                    y: y - 1,
-                                })),
-                                Bound::Excluded(YFirstIVec2(position)),
-                            ))
-                            .last()
-                            .map(|&YFirstIVec2(position)| position),
-                    };
-
-                    next_obstructions.insert((position, direction), next_obstruction);
-                }
-            }
+    let mut guard_table = Table::new(
+        "Guards",
+        [
+            "Guard",
+            "X",
+            "Y",
+            "Heading",
+            "Visited",
+            "Outcome",
+            "Crosses paths with",
+        ],
+    );
+
+    let visited_positions: Vec<HashSet<IVec2>> = lab
+        .guards
+        .iter()
+        .map(|&guard| lab.visited_positions(guard))
+        .collect();
+
+    for (guard_index, &guard) in lab.guards.iter().enumerate() {
+        let outcome = lab
+            .patrol(guard)
+            .last()
+            .map(|step| format!("{:?}", step.event))
+            .unwrap_or_default();
+
+        let crossed_guards: Vec<String> = visited_positions
+            .iter()
+            .enumerate()
+            .filter(|&(other_index, other_visited_positions)| {
+                other_index != guard_index
+                    && !other_visited_positions.is_disjoint(&visited_positions[guard_index])
+            })
+            .map(|(other_index, _)| (other_index + 1).to_string())
+            .collect();
+
+        guard_table.push_row([
+            (guard_index + 1).to_string(),
+            guard.start_position.x.to_string(),
+            guard.start_position.y.to_string(),
+            guard_char(guard.direction).to_string(),
+            visited_positions[guard_index].len().to_string(),
+            outcome,
+            crossed_guards.join(" "),
+        ]);
+    }
+
+    let mut loop_table = Table::new(
+        "Loop-causing obstructions",
+        ["Guard", "X", "Y", "Turns", "Loop"],
+    );
+
+    let mut loop_obstruction_count = 0;
+
+    for (guard_index, &guard) in lab.guards.iter().enumerate() {
+        for (obstruction_position, loop_turns) in lab.loop_obstructions(guard) {
+            let loop_turns: Vec<String> = loop_turns
+                .iter()
+                .map(|turn| {
+                    format!(
+                        "{},{}{}",
+                        turn.position.x,
+                        turn.position.y,
+                        guard_char(turn.direction)
+                    )
+                })
+                .collect();
+
+            loop_table.push_row([
+                (guard_index + 1).to_string(),
+                obstruction_position.x.to_string(),
+                obstruction_position.y.to_string(),
+                loop_turns.len().to_string(),
+                loop_turns.join(" "),
+            ]);
+
+            loop_obstruction_count += 1;
         }
     }
 
-    let next_obstructions = &next_obstructions;
+    loop_table.set_footer([
+        String::from("Total"),
+        String::new(),
+        String::new(),
+        String::new(),
+        loop_obstruction_count.to_string(),
+    ]);
 
-    let start_position = start_position.into_inner().unwrap();
+    Ok(vec![guard_table, trace_table, loop_table])
+}
 
-    let visited_positions = {
-        let mut visited_positions = lab.visited_positions(start_position);
-        visited_positions.remove(&start_position);
-        visited_positions
-    };
+#[cfg(test)]
+mod tests {
+    use fastrand::Rng;
 
-    let mut looped_routes = 0;
-
-    for new_obstruction_position in visited_positions {
-        let current_position = Cell::new(start_position);
-        let direction = Cell::new(Direction::North);
-
-        let mut seen_obstructions: HashSet<(IVec2, Direction)> = HashSet::new();
-
-        let new_obstruction_position = || match direction.get() {
-            Direction::East => (new_obstruction_position.x > current_position.get().x
-                && new_obstruction_position.y == current_position.get().y)
-                .then_some(new_obstruction_position),
-            Direction::North => (new_obstruction_position.x == current_position.get().x
-                && new_obstruction_position.y < current_position.get().y)
-                .then_some(new_obstruction_position),
-            Direction::South => (new_obstruction_position.x == current_position.get().x
-                && new_obstruction_position.y > current_position.get().y)
-                .then_some(new_obstruction_position),
-            Direction::West => (new_obstruction_position.x < current_position.get().x
-                && new_obstruction_position.y == current_position.get().y)
-                .then_some(new_obstruction_position),
-        };
+    use super::*;
 
-        let next_obstruction = || match (
-            new_obstruction_position(),
-            next_obstructions[&(current_position.get(), direction.get())],
-        ) {
-            (None, None) => None,
-            (None, Some(position)) | (Some(position), None) => Some(position),
-            (Some(new_position), Some(next_position)) => Some(match direction.get() {
-                Direction::East => {
-                    if new_position.x < next_position.x {
-                        new_position
-                    } else {
-                        next_position
-                    }
-                }
-                Direction::North => {
-                    if new_position.y > next_position.y {
-                        new_position
-                    } else {
-                        next_position
-                    }
-                }
-                Direction::South => {
-                    if new_position.y < next_position.y {
-                        new_position
-                    } else {
-                        next_position
-                    }
-                }
-                Direction::West => {
-                    if new_position.x > next_position.x {
-                        new_position
-                    } else {
-                        next_position
-                    }
-                }
-            }),
-        };
+    /// A random lab of up to 12 by 12 positions, with about a fifth of them obstructed, and a
+    /// single guard facing any direction.
+    fn random_lab(rng: &mut Rng) -> String {
+        let width = rng.usize(1..=12);
+        let height = rng.usize(1..=12);
 
-        let mut n = next_obstruction();
+        let mut cells: Vec<char> = (0..width * height)
+            .map(|_| if rng.u8(0..5) == 0 { '#' } else { '.' })
+            .collect();
 
-        while let Some(obstruction_position) = n {
-            if !seen_obstructions.insert((obstruction_position, direction.get())) {
-                looped_routes += 1;
-                break;
-            }
+        let guard_index = rng.usize(..cells.len());
+        cells[guard_index] = guard_char(Direction::ALL[rng.usize(..4)]);
+
+        cells
+            .chunks(width)
+            .map(|row| row.iter().collect::<String>() + "\n")
+            .collect()
+    }
+
+    #[test]
+    fn fast_loop_obstructions_match_simulation() {
+        let mut rng = Rng::with_seed(6);
+        let mut loop_count = 0;
 
-            current_position.set(obstruction_position - direction.get().xy());
-            direction.set(direction.get().next());
+        for _ in 0..500 {
+            let input = random_lab(&mut rng);
+            let lab: Lab = input.parse().unwrap();
+            let guard = lab.single_guard().unwrap();
 
-            n = next_obstruction();
+            let expected: Vec<IVec2> = lab
+                .loop_obstructions(guard)
+                .into_iter()
+                .map(|(position, _)| position)
+                .collect();
+
+            assert_eq!(lab.fast_loop_obstructions(guard), expected, "Lab:\n{input}");
+
+            loop_count += expected.len();
         }
-    }
 
-    Ok(looped_routes)
+        assert!(loop_count > 0, "No random lab had a loop obstruction.");
+    }
 }
@@ -1,14 +1,102 @@
-use std::{
-    num::ParseIntError,
-    ops::{AddAssign, MulAssign},
-    str::FromStr,
+use adventofcode::{
+    env_choice,
+    parse::{self, Line, ParseError},
+    report::Table,
+    solve_or_diagnose_day,
 };
-
-use adventofcode::solve_day;
-use anyhow::{anyhow, bail};
+use anyhow::anyhow;
 
 fn main() -> anyhow::Result<()> {
-    solve_day(file!(), part1, part2)
+    solve_or_diagnose_day(file!(), part1, part2, diagnose)
+}
+
+/// An operator which can be placed between two operands of an equation. Operators are always
+/// evaluated left to right.
+///
+/// Any result which doesn't fit in a `u64` is treated as not existing, so that overflow prunes a
+/// branch of the search rather than wrapping or panicking.
+trait Operator {
+    /// How the operator is written in an expression.
+    fn symbol(&self) -> &'static str;
+
+    /// Combines two operands, if the result doesn't overflow.
+    fn apply(&self, left: u64, right: u64) -> Option<u64>;
+
+    /// The left operands which, combined with `right`, give `result`, if there are any.
+    fn invert(&self, result: u64, right: u64) -> Option<LeftOperand>;
+}
+
+/// The left operands from which an operator could have produced a result.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum LeftOperand {
+    /// Only this left operand produces the result.
+    Exactly(u64),
+    /// Every left operand produces the result, e.g. when multiplying by 0.
+    Any,
+}
+
+struct Add;
+
+impl Operator for Add {
+    fn symbol(&self) -> &'static str {
+        "+"
+    }
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_add(right)
+    }
+
+    fn invert(&self, result: u64, right: u64) -> Option<LeftOperand> {
+        result.checked_sub(right).map(LeftOperand::Exactly)
+    }
+}
+
+struct Multiply;
+
+impl Operator for Multiply {
+    fn symbol(&self) -> &'static str {
+        "*"
+    }
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_mul(right)
+    }
+
+    fn invert(&self, result: u64, right: u64) -> Option<LeftOperand> {
+        match (result, right) {
+            // Multiplying anything by 0 gives 0.
+            (0, 0) => Some(LeftOperand::Any),
+            (_, 0) => None,
+            _ => result
+                .is_multiple_of(right)
+                .then(|| LeftOperand::Exactly(result / right)),
+        }
+    }
+}
+
+/// Joins the digits of the left operand with the digits of the right operand.
+struct Concatenate;
+
+impl Operator for Concatenate {
+    fn symbol(&self) -> &'static str {
+        "||"
+    }
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        let next_power_of_ten =
+            10_u64.checked_pow(right.checked_ilog10().unwrap_or_default() + 1)?;
+
+        left.checked_mul(next_power_of_ten)?.checked_add(right)
+    }
+
+    fn invert(&self, result: u64, right: u64) -> Option<LeftOperand> {
+        // 0 has a single digit, so concatenating it means the last digit is 0.
+        let next_power_of_ten =
+            10_u64.checked_pow(right.checked_ilog10().unwrap_or_default() + 1)?;
+
+        (result % next_power_of_ten == right)
+            .then(|| LeftOperand::Exactly(result / next_power_of_ten))
+    }
 }
 
 struct Equation {
@@ -16,25 +104,22 @@ struct Equation {
     test_value: u64,
 }
 
-impl FromStr for Equation {
-    type Err = anyhow::Error;
+impl TryFrom<Line<'_>> for Equation {
+    type Error = ParseError;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (test_value, operands) = s
-            .split_once(':')
-            .ok_or(anyhow!("Equation was missing a `:` character"))?;
-
-        let test_value: u64 = test_value.parse()?;
-
-        let operands: Result<Vec<u64>, ParseIntError> =
-            operands.split_whitespace().map(str::parse::<u64>).collect();
+    fn try_from(line: Line<'_>) -> Result<Self, Self::Error> {
+        if !line.contains(':') {
+            return Err(line.error("Equation was missing a `:` character"));
+        }
 
-        let operands = operands?;
+        let mut operands: Vec<u64> = line.integers()?;
 
-        if operands.len() < 2 {
-            bail!("An equation should have at least 2 operands.")
+        if operands.len() < 3 {
+            return Err(line.error("An equation should have a test value and at least 2 operands."));
         }
 
+        let test_value = operands.remove(0);
+
         Ok(Self {
             operands,
             test_value,
@@ -42,104 +127,403 @@ impl FromStr for Equation {
     }
 }
 
-fn part1(input: &str) -> anyhow::Result<u64> {
-    let equations: anyhow::Result<Vec<Equation>> =
-        input.lines().map(str::parse::<Equation>).collect();
+impl Equation {
+    /// Whether some choice of `operators` between the operands gives the test value.
+    fn is_satisfiable(&self, operators: &[&dyn Operator]) -> bool {
+        Self::can_reach(self.test_value, &self.operands, operators)
+    }
 
-    let equations = equations?;
+    /// Works backwards from `target`, undoing each possible operator on the last operand in turn.
+    /// Any operator which can't be undone rules out every combination of operators ending with it,
+    /// so the search is pruned from the right.
+    fn can_reach(target: u64, operands: &[u64], operators: &[&dyn Operator]) -> bool {
+        let Some((&last_operand, operands)) = operands.split_last() else {
+            return false;
+        };
 
-    let mut total_calibration_result = 0;
+        if operands.is_empty() {
+            return last_operand == target;
+        }
 
-    for equation in equations {
-        let operator_count = equation.operands.len() - 1;
+        operators
+            .iter()
+            .any(|operator| match operator.invert(target, last_operand) {
+                Some(LeftOperand::Exactly(target)) => Self::can_reach(target, operands, operators),
+                Some(LeftOperand::Any) => {
+                    Self::can_evaluate(operands[0], &operands[1..], operators)
+                }
+                None => false,
+            })
+    }
 
-        let operator_combinations = 2_u16.pow(operator_count as u32);
+    /// Whether some choice of operators between `total` and the remaining `operands` evaluates
+    /// without overflowing.
+    fn can_evaluate(total: u64, operands: &[u64], operators: &[&dyn Operator]) -> bool {
+        let Some((&operand, operands)) = operands.split_first() else {
+            return true;
+        };
+
+        operators.iter().any(|operator| {
+            operator
+                .apply(total, operand)
+                .is_some_and(|total| Self::can_evaluate(total, operands, operators))
+        })
+    }
 
-        for operator_bitset in 0..operator_combinations {
-            let mut total = equation.operands[0];
+    /// Every choice of operators between `total` and the remaining `operands` which evaluates
+    /// without overflowing, each appended to `chosen_operators`, which holds the operators chosen
+    /// so far from left to right.
+    fn find_evaluations(
+        total: u64,
+        operands: &[u64],
+        operators: &[&dyn Operator],
+        chosen_operators: &mut Vec<usize>,
+        evaluations: &mut Vec<Vec<usize>>,
+    ) {
+        let Some((&operand, operands)) = operands.split_first() else {
+            evaluations.push(chosen_operators.clone());
+            return;
+        };
+
+        for (operator_index, operator) in operators.iter().enumerate() {
+            if let Some(total) = operator.apply(total, operand) {
+                chosen_operators.push(operator_index);
+                Self::find_evaluations(total, operands, operators, chosen_operators, evaluations);
+                chosen_operators.pop();
+            }
+        }
+    }
 
-            for offset in (0..operator_count).rev() {
-                let next_operand = equation.operands[operator_count - offset];
+    /// Every choice of operators which gives the test value, each as the indices into `operators`
+    /// of the operators between each pair of operands, from left to right.
+    fn solutions(&self, operators: &[&dyn Operator]) -> Vec<Vec<usize>> {
+        let mut solutions = Vec::new();
 
-                let operator_check = operator_bitset & (1 << offset);
+        Self::find_solutions(
+            self.test_value,
+            &self.operands,
+            operators,
+            &mut Vec::new(),
+            &mut solutions,
+        );
 
-                let operator = if operator_check == 0 {
-                    <u64 as MulAssign<u64>>::mul_assign
-                } else {
-                    AddAssign::add_assign
-                };
+        solutions
+    }
+
+    /// As [`Equation::can_reach`], but records every way of reaching `target` in `solutions`
+    /// rather than stopping at the first. `chosen_operators` holds the operators chosen so far,
+    /// from right to left.
+    fn find_solutions(
+        target: u64,
+        operands: &[u64],
+        operators: &[&dyn Operator],
+        chosen_operators: &mut Vec<usize>,
+        solutions: &mut Vec<Vec<usize>>,
+    ) {
+        let Some((&last_operand, operands)) = operands.split_last() else {
+            return;
+        };
+
+        if operands.is_empty() {
+            if last_operand == target {
+                solutions.push(chosen_operators.iter().rev().copied().collect());
+            }
 
-                operator(&mut total, next_operand);
+            return;
+        }
 
-                if total > equation.test_value {
-                    break;
+        for (operator_index, operator) in operators.iter().enumerate() {
+            match operator.invert(target, last_operand) {
+                Some(LeftOperand::Exactly(target)) => {
+                    chosen_operators.push(operator_index);
+                    Self::find_solutions(target, operands, operators, chosen_operators, solutions);
+                    chosen_operators.pop();
+                }
+                Some(LeftOperand::Any) => {
+                    // Every way of evaluating the remaining operands leads to a solution.
+                    let mut evaluations = Vec::new();
+
+                    Self::find_evaluations(
+                        operands[0],
+                        &operands[1..],
+                        operators,
+                        &mut Vec::new(),
+                        &mut evaluations,
+                    );
+
+                    for mut evaluation in evaluations {
+                        evaluation.push(operator_index);
+                        evaluation.extend(chosen_operators.iter().rev());
+                        solutions.push(evaluation);
+                    }
                 }
+                None => {}
             }
+        }
+    }
 
-            if total == equation.test_value {
-                total_calibration_result += equation.test_value;
-                break;
-            }
+    /// Writes out the equation with the operators at `operator_indices` between its operands,
+    /// e.g. `3267: 81 + 40 * 27`.
+    fn render(&self, operators: &[&dyn Operator], operator_indices: &[usize]) -> String {
+        let mut expression = format!("{}: {}", self.test_value, self.operands[0]);
+
+        for (&operator_index, operand) in operator_indices.iter().zip(&self.operands[1..]) {
+            expression.push_str(&format!(
+                " {} {operand}",
+                operators[operator_index].symbol()
+            ));
         }
+
+        expression
     }
+}
 
-    Ok(total_calibration_result)
+/// Which satisfying expressions are listed for each equation by [`diagnose`].
+///
+/// Defaults to [`ExpressionListing::First`], and can be overridden by setting
+/// [`ExpressionListing::ENV_VAR`] to `first` or `all`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ExpressionListing {
+    First,
+    All,
 }
 
-fn part2(input: &str) -> anyhow::Result<u64> {
-    let equations: anyhow::Result<Vec<Equation>> =
-        input.lines().map(str::parse::<Equation>).collect();
+impl ExpressionListing {
+    const ENV_VAR: &str = "AOC_DAY7_EXPRESSIONS";
 
-    let equations = equations?;
+    fn from_env() -> anyhow::Result<Self> {
+        env_choice(
+            Self::ENV_VAR,
+            "Expression listing",
+            &[("first", Self::First), ("all", Self::All)],
+        )
+    }
+}
+
+const PART1_OPERATORS: [&dyn Operator; 2] = [&Add, &Multiply];
+const PART2_OPERATORS: [&dyn Operator; 3] = [&Add, &Multiply, &Concatenate];
+
+fn parse_equations(input: &str) -> Result<Vec<(Line<'_>, Equation)>, ParseError> {
+    parse::lines(input)
+        .map(|line| Ok((line, Equation::try_from(line)?)))
+        .collect()
+}
+
+/// The sum of the test values of every equation which can be satisfied using `operators`.
+fn total_calibration_result(input: &str, operators: &[&dyn Operator]) -> anyhow::Result<u64> {
+    parse_equations(input)?
+        .iter()
+        .filter(|(_, equation)| equation.is_satisfiable(operators))
+        .try_fold(0_u64, |total, (_, equation)| {
+            total.checked_add(equation.test_value)
+        })
+        .ok_or(anyhow!("The total calibration result overflowed."))
+}
+
+fn part1(input: &str) -> anyhow::Result<u64> {
+    total_calibration_result(input, &PART1_OPERATORS)
+}
 
-    let mut total_calibration_result = 0;
+fn part2(input: &str) -> anyhow::Result<u64> {
+    total_calibration_result(input, &PART2_OPERATORS)
+}
 
-    for equation in equations {
-        let operator_count = equation.operands.len() - 1; // 2
+/// Lists an expression which satisfies each equation for each part, or every such expression
+/// depending on [`ExpressionListing::ENV_VAR`], along with how many there are.
+fn diagnose(input: &str) -> anyhow::Result<Vec<Table>> {
+    let equations = parse_equations(input)?;
+    let expression_listing = ExpressionListing::from_env()?;
+
+    let mut tables = Vec::new();
+
+    for (part, operators) in [(1, &PART1_OPERATORS[..]), (2, &PART2_OPERATORS[..])] {
+        let mut table = Table::new(
+            format!("Part {part}: satisfying expressions"),
+            ["Line", "Solutions", "Expression"],
+        );
+
+        let mut total_calibration_result: u64 = 0;
+
+        for (line, equation) in &equations {
+            let solutions = equation.solutions(operators);
+
+            let Some(first_solution) = solutions.first() else {
+                continue;
+            };
+
+            total_calibration_result = total_calibration_result
+                .checked_add(equation.test_value)
+                .ok_or(anyhow!("The total calibration result overflowed."))?;
+
+            table.push_row([
+                line.number().to_string(),
+                solutions.len().to_string(),
+                equation.render(operators, first_solution),
+            ]);
+
+            if expression_listing == ExpressionListing::All {
+                for solution in &solutions[1..] {
+                    table.push_row([
+                        String::new(),
+                        String::new(),
+                        equation.render(operators, solution),
+                    ]);
+                }
+            }
+        }
 
-        let operator_combinations = 3_u32.pow(operator_count as u32); // 9
+        table.set_footer([
+            String::from("Total"),
+            String::new(),
+            total_calibration_result.to_string(),
+        ]);
 
-        for mut operator_tritset in 0..operator_combinations {
-            let mut total = equation.operands[0];
+        tables.push(table);
+    }
 
-            for offset in (0..operator_count).rev() {
-                // 0 or 1
-                let next_operand = equation.operands[operator_count - offset];
+    Ok(tables)
+}
 
-                // 000 001 002 010 011 012 020 021 022
-                //  00  01  02  10  11  12  20  21  22
+#[cfg(test)]
+mod tests {
+    use fastrand::Rng;
+
+    use super::*;
+
+    /// Equations with operands and results near `u64::MAX`, along with whether they can be
+    /// satisfied using the part 2 operators, where any overflow must be treated as a dead end.
+    const OVERFLOW_CASES: [(&str, bool); 7] = [
+        // Only `*` works, since `+` overflows.
+        ("18446744073709551615: 18446744073709551615 1", true),
+        ("18446744073709551615: 18446744073709551614 1", true),
+        // 2^32 * 2^32 is exactly one more than `u64::MAX`.
+        ("18446744073709551615: 4294967296 4294967296", false),
+        ("18446744073709551615: 1844674407370955161 5", true),
+        // Concatenating gives one more than `u64::MAX`.
+        ("18446744073709551615: 1844674407370955161 6", false),
+        // Every operator overflows.
+        ("1: 18446744073709551615 18446744073709551615", false),
+        // An overflowing prefix must not wrap around to a small value which is then added to.
+        ("2: 9223372036854775808 2 2", false),
+    ];
+
+    impl Equation {
+        /// Evaluates the equation's operands left to right with the operators at
+        /// `operator_indices` between them, or `None` if any step overflows.
+        fn evaluate(&self, operators: &[&dyn Operator], operator_indices: &[usize]) -> Option<u64> {
+            operator_indices.iter().zip(&self.operands[1..]).try_fold(
+                self.operands[0],
+                |total, (&operator_index, &operand)| {
+                    operators[operator_index].apply(total, operand)
+                },
+            )
+        }
 
-                let operator_check = operator_tritset / 3_u32.pow(offset as u32);
-                operator_tritset %= 3_u32.pow(offset as u32);
+        /// The number of choices of operators which give the test value, found by evaluating
+        /// every possible choice from left to right. Used to check [`Equation::is_satisfiable`]
+        /// and [`Equation::solutions`].
+        fn brute_force_solution_count(&self, operators: &[&dyn Operator]) -> usize {
+            let operator_count = self.operands.len() - 1;
 
-                fn concatenate_int(concatenatee: &mut u64, concatenator: u64) {
-                    let next_power_of_ten = 10_u64.pow(concatenator.ilog10() + 1);
+            let mut operator_indices = vec![0; operator_count];
+            let mut solution_count = 0;
 
-                    *concatenatee *= next_power_of_ten;
-                    *concatenatee += concatenator
+            loop {
+                if self.evaluate(operators, &operator_indices) == Some(self.test_value) {
+                    solution_count += 1;
                 }
 
-                let operator = if operator_check == 0 {
-                    concatenate_int
-                } else if operator_check == 1 {
-                    MulAssign::mul_assign
-                } else {
-                    AddAssign::add_assign
+                // Count upwards in base `operators.len()`, one digit per operator.
+                let Some(carry_index) = operator_indices
+                    .iter()
+                    .rposition(|&operator_index| operator_index + 1 < operators.len())
+                else {
+                    break solution_count;
                 };
 
-                operator(&mut total, next_operand);
+                operator_indices[carry_index] += 1;
+                operator_indices[carry_index + 1..].fill(0);
+            }
+        }
+    }
+
+    fn parse_equation(equation: &str) -> Equation {
+        let [(_, equation)] = parse_equations(equation).unwrap().try_into().ok().unwrap();
 
-                if total > equation.test_value {
-                    break;
-                }
+        equation
+    }
+
+    /// A short equation with small operands, including 0, whose test value is either reachable
+    /// with the part 2 operators or random.
+    fn random_equation(rng: &mut Rng) -> Equation {
+        let operands: Vec<u64> = (0..rng.usize(2..=6)).map(|_| rng.u64(0..=12)).collect();
+
+        let mut equation = Equation {
+            operands,
+            test_value: rng.u64(0..=200),
+        };
+
+        if rng.bool() {
+            let operator_indices: Vec<usize> = (1..equation.operands.len())
+                .map(|_| rng.usize(0..PART2_OPERATORS.len()))
+                .collect();
+
+            if let Some(test_value) = equation.evaluate(&PART2_OPERATORS, &operator_indices) {
+                equation.test_value = test_value;
             }
+        }
+
+        equation
+    }
 
-            if total == equation.test_value {
-                total_calibration_result += equation.test_value;
-                break;
+    #[test]
+    fn search_matches_brute_force() {
+        let mut rng = Rng::with_seed(7);
+
+        for _ in 0..5000 {
+            let equation = random_equation(&mut rng);
+
+            for operators in [&PART1_OPERATORS[..], &PART2_OPERATORS[..]] {
+                let solution_count = equation.brute_force_solution_count(operators);
+                let solutions = equation.solutions(operators);
+
+                assert_eq!(
+                    equation.is_satisfiable(operators),
+                    solution_count > 0,
+                    "Equation {}: {:?}",
+                    equation.test_value,
+                    equation.operands
+                );
+
+                assert_eq!(solutions.len(), solution_count);
+
+                for operator_indices in solutions {
+                    assert_eq!(
+                        equation.evaluate(operators, &operator_indices),
+                        Some(equation.test_value),
+                        "{}",
+                        equation.render(operators, &operator_indices)
+                    );
+                }
             }
         }
     }
 
-    Ok(total_calibration_result)
+    #[test]
+    fn overflow_is_a_dead_end() {
+        for (equation_text, expected) in OVERFLOW_CASES {
+            let equation = parse_equation(equation_text);
+
+            assert_eq!(
+                equation.is_satisfiable(&PART2_OPERATORS),
+                expected,
+                "{equation_text}"
+            );
+            assert_eq!(
+                equation.brute_force_solution_count(&PART2_OPERATORS) > 0,
+                expected,
+                "{equation_text}"
+            );
+        }
+    }
 }
@@ -1,12 +1,16 @@
 use std::{
     cell::OnceCell,
     collections::{HashMap, HashSet},
-    iter,
-    ops::Index,
+    ops::RangeInclusive,
+    str::FromStr,
 };
 
-use adventofcode::solve_day;
-use anyhow::ensure;
+use adventofcode::{
+    grid::Grid,
+    parse::{self, ParseError},
+    solve_day, variant_var,
+};
+use anyhow::{bail, ensure};
 use glam::IVec2;
 
 fn main() -> anyhow::Result<()> {
@@ -15,53 +19,25 @@ fn main() -> anyhow::Result<()> {
 
 struct City {
     antenna_positions: OnceCell<HashMap<Antenna, Vec<IVec2>>>,
-    col_count: i32,
-    rows: Vec<Vec<Option<Antenna>>>,
-    row_count: i32,
+    map: Grid<Option<Antenna>>,
 }
 
 #[derive(Clone, Copy, Eq, Hash, PartialEq)]
 struct Antenna(char);
 
-impl Index<usize> for City {
-    type Output = Vec<Option<Antenna>>;
-
-    fn index(&self, index: usize) -> &Self::Output {
-        self.rows.index(index)
-    }
-}
+impl FromStr for City {
+    type Err = ParseError;
 
-impl<I2: IntoIterator<Item = char>> FromIterator<I2> for City {
-    fn from_iter<I1>(iter: I1) -> Self
-    where
-        I1: IntoIterator<Item = I2>,
-    {
-        let rows: Vec<Vec<Option<Antenna>>> = iter
-            .into_iter()
-            .map(|char_iter| {
-                char_iter
-                    .into_iter()
-                    .map(|char| Antenna::try_from(char).ok())
-                    .collect()
-            })
-            .collect();
-
-        assert!(!rows.is_empty(), "No rows in city.");
-
-        let row_count = rows.len();
-        let col_count = rows[0].len();
-
-        assert!(
-            rows[1..].iter().all(|row| row.len() == col_count),
-            "City rows are not all of equal length."
-        );
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Anything which isn't an antenna is an empty space.
+        let map = parse::grid(parse::lines(s), |_, char| {
+            Ok::<_, &str>(Antenna::try_from(char).ok())
+        })?;
 
-        Self {
+        Ok(Self {
             antenna_positions: OnceCell::new(),
-            col_count: col_count as i32,
-            rows,
-            row_count: row_count as i32,
-        }
+            map,
+        })
     }
 }
 
@@ -70,21 +46,12 @@ impl City {
         self.antenna_positions.get_or_init(|| {
             let mut antenna_positions: HashMap<Antenna, Vec<IVec2>> = HashMap::new();
 
-            for (y, row) in self.rows.iter().enumerate() {
-                let y = y as i32;
-
-                for (x, &antenna) in row.iter().enumerate() {
-                    let x = x as i32;
+            for (position, &antenna) in self.map.iter() {
+                let Some(antenna) = antenna else {
+                    continue;
+                };
 
-                    let Some(antenna) = antenna else {
-                        continue;
-                    };
-
-                    antenna_positions
-                        .entry(antenna)
-                        .or_default()
-                        .push(IVec2 { x, y });
-                }
+                antenna_positions.entry(antenna).or_default().push(position);
             }
 
             antenna_positions
@@ -92,7 +59,7 @@ impl City {
     }
 
     fn in_bounds(&self, position: IVec2) -> bool {
-        position.min_element() >= 0 && position.x < self.col_count && position.y < self.row_count
+        self.map.in_bounds(position)
     }
 }
 
@@ -108,34 +75,125 @@ impl TryFrom<char> for Antenna {
         Ok(Self(value))
     }
 }
-fn part1(input: &str) -> anyhow::Result<u64> {
-    let city: City = input.lines().map(|row| row.chars()).collect();
 
-    let mut antinode_positions: HashSet<IVec2> = HashSet::new();
+/// Which positions along the line through a pair of antennas with the same frequency are
+/// antinodes.
+///
+/// Part 2's resonance can be overridden at runtime by setting [`Resonance::ENV_VAR`] to e.g.
+/// `harmonics=2-5,lattice=false`. Any omitted setting keeps part 2's value, and the answers are
+/// flagged as being for a variant.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Resonance {
+    /// Which multiples of the step, measured outwards from each antenna, are antinodes, where the
+    /// 0th harmonic is the antenna itself. Harmonics are only generated until they leave the city.
+    harmonics: RangeInclusive<i32>,
+    /// Whether to step by the difference between the antennas divided by its gcd, rather than the
+    /// whole difference. This also makes every lattice point between the antennas an antinode.
+    lattice: bool,
+}
 
-    for positions in city
-        .antenna_positions()
-        .values()
-        .filter(|positions| positions.len() > 1)
-    {
-        for (position_a_index, position_a) in positions[..positions.len()].iter().enumerate() {
-            for position_b in &positions[(position_a_index + 1)..] {
-                let diff = position_a - position_b;
-
-                antinode_positions.extend(
-                    [position_a + diff, position_b - diff]
-                        .into_iter()
-                        .filter(|&position| city.in_bounds(position)),
-                );
+impl Resonance {
+    const ENV_VAR: &str = "AOC_DAY8_RESONANCE";
+
+    /// One antinode on each side of the pair, twice as far from one antenna as the other.
+    const PART1: Self = Self {
+        harmonics: 1..=1,
+        lattice: false,
+    };
+
+    /// Every grid position in line with the pair.
+    const PART2: Self = Self {
+        harmonics: 0..=i32::MAX,
+        lattice: true,
+    };
+
+    fn part2_from_env() -> anyhow::Result<Self> {
+        match variant_var(Self::ENV_VAR) {
+            Some(resonance) => resonance.parse(),
+            None => Ok(Self::PART2),
+        }
+    }
+
+    /// The in-bounds antinodes produced by antennas at `position_a` and `position_b`.
+    fn antinodes(&self, city: &City, position_a: IVec2, position_b: IVec2) -> Vec<IVec2> {
+        let diff = position_a - position_b;
+
+        let step_count = if self.lattice {
+            gcd(diff.x.abs(), diff.y.abs())
+        } else {
+            1
+        };
+        let step = diff / step_count;
+
+        let mut antinodes = Vec::new();
+
+        // Walk outwards from each antenna, away from the other.
+        for (position, step) in [(position_a, step), (position_b, -step)] {
+            antinodes.extend(
+                self.harmonics
+                    .clone()
+                    .map_while(|harmonic| harmonic_position(position, step, harmonic))
+                    .take_while(|&position| city.in_bounds(position)),
+            );
+        }
+
+        // Any lattice points strictly between the antennas.
+        if self.lattice {
+            antinodes.extend((1..step_count).map(|step_index| position_b + step * step_index));
+        }
+
+        antinodes
+    }
+}
+
+impl FromStr for Resonance {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut resonance = Self::PART2;
+
+        for setting in parse::settings(s) {
+            let setting = setting?;
+
+            match setting.name() {
+                "harmonics" => {
+                    resonance.harmonics = setting.range()?;
+
+                    ensure!(
+                        *resonance.harmonics.start() >= 0,
+                        "Harmonics should not be negative."
+                    );
+                }
+                "lattice" => resonance.lattice = setting.parse()?,
+                name => bail!("Unknown resonance setting `{name}`."),
             }
         }
+
+        Ok(resonance)
     }
+}
 
-    Ok(antinode_positions.len() as u64)
+/// The position `harmonic` steps from `position`, or `None` if it overflows, in which case it's
+/// certainly outside the city.
+fn harmonic_position(position: IVec2, step: IVec2, harmonic: i32) -> Option<IVec2> {
+    Some(IVec2::new(
+        position.x.checked_add(step.x.checked_mul(harmonic)?)?,
+        position.y.checked_add(step.y.checked_mul(harmonic)?)?,
+    ))
 }
 
-fn part2(input: &str) -> anyhow::Result<u64> {
-    let city: City = input.lines().map(|row| row.chars()).collect();
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// The number of distinct in-bounds positions which are antinodes of any pair of antennas with
+/// the same frequency.
+fn count_antinodes(input: &str, resonance: &Resonance) -> anyhow::Result<u64> {
+    let city: City = input.parse()?;
 
     let mut antinode_positions: HashSet<IVec2> = HashSet::new();
 
@@ -144,21 +202,20 @@ fn part2(input: &str) -> anyhow::Result<u64> {
         .values()
         .filter(|positions| positions.len() > 1)
     {
-        for (position_a_index, &position_a) in positions[..positions.len()].iter().enumerate() {
+        for (position_a_index, &position_a) in positions.iter().enumerate() {
             for &position_b in &positions[(position_a_index + 1)..] {
-                let diff = position_a - position_b;
-
-                antinode_positions.extend(iter::successors(Some(position_a), |&position| {
-                    let next_position = position + diff;
-                    city.in_bounds(next_position).then_some(next_position)
-                }));
-                antinode_positions.extend(iter::successors(Some(position_b), |&position| {
-                    let next_position = position - diff;
-                    city.in_bounds(next_position).then_some(next_position)
-                }));
+                antinode_positions.extend(resonance.antinodes(&city, position_a, position_b));
             }
         }
     }
 
     Ok(antinode_positions.len() as u64)
 }
+
+fn part1(input: &str) -> anyhow::Result<u64> {
+    count_antinodes(input, &Resonance::PART1)
+}
+
+fn part2(input: &str) -> anyhow::Result<u64> {
+    count_antinodes(input, &Resonance::part2_from_env()?)
+}
@@ -1,7 +1,9 @@
 use std::{cmp::Ordering, fmt};
 
-use adventofcode::solve_day;
-use anyhow::anyhow;
+use adventofcode::{
+    parse::{self, ParseError},
+    solve_day,
+};
 
 fn main() -> anyhow::Result<()> {
     solve_day(file!(), part1, part2)
@@ -60,25 +62,35 @@ impl Block {
     }
 }
 
-fn part1(input: &str) -> anyhow::Result<u64> {
+/// Reads the disk map into alternating file and free chunks, also returning the block after the
+/// last chunk.
+fn parse_disk(input: &str) -> Result<(Vec<Chunk>, Block), ParseError> {
     let mut current_block = Block::default();
 
     let mut disk: Vec<Chunk> = Vec::with_capacity(input.len());
 
-    for char in input.trim_ascii_end().chars() {
-        let block_size = u64::from(
-            char.to_digit(10)
-                .ok_or(anyhow!("Attempted to parse char outside the range 0-9"))?,
-        );
+    for line in parse::lines(input) {
+        for (x, char) in line.chars().enumerate() {
+            let block_size = u64::from(
+                char.to_digit(10)
+                    .ok_or(line.error_at(x, "Attempted to parse char outside the range 0-9"))?,
+            );
 
-        disk.push(Chunk {
-            block: current_block,
-            length: block_size,
-        });
+            disk.push(Chunk {
+                block: current_block,
+                length: block_size,
+            });
 
-        current_block = current_block.next();
+            current_block = current_block.next();
+        }
     }
 
+    Ok((disk, current_block))
+}
+
+fn part1(input: &str) -> anyhow::Result<u64> {
+    let (mut disk, _) = parse_disk(input)?;
+
     while let Some(first_free_chunk_index) = disk
         .iter()
         .position(|chunk| chunk.block.block_type == BlockType::Free)
@@ -125,23 +137,7 @@ fn part1(input: &str) -> anyhow::Result<u64> {
 }
 
 fn part2(input: &str) -> anyhow::Result<u64> {
-    let mut current_block = Block::default();
-
-    let mut disk: Vec<Chunk> = Vec::with_capacity(input.len());
-
-    for char in input.trim_ascii_end().chars() {
-        let block_size = u64::from(
-            char.to_digit(10)
-                .ok_or(anyhow!("Attempted to parse char outside the range 0-9"))?,
-        );
-
-        disk.push(Chunk {
-            block: current_block,
-            length: block_size,
-        });
-
-        current_block = current_block.next();
-    }
+    let (mut disk, current_block) = parse_disk(input)?;
 
     for file_id in (1..=current_block.id).rev() {
         // The chunk with the given `file_id` is guaranteed to exist in `disk`.
@@ -0,0 +1,169 @@
+use std::ops::{Index, IndexMut};
+
+use glam::IVec2;
+
+mod render;
+mod search;
+
+pub use render::{debug_enabled, Color, Renderer, DEBUG_VAR};
+pub use search::Region;
+
+/// A rectangular grid of cells, indexed by `IVec2` positions where `x` is the column and `y` is
+/// the row. As with the puzzle inputs, -y is up.
+pub struct Grid<T> {
+    col_count: i32,
+    rows: Vec<Vec<T>>,
+    row_count: i32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Direction {
+    East,
+    North,
+    South,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Self; 4] = [Self::East, Self::North, Self::South, Self::West];
+
+    pub fn inverse(self) -> Self {
+        match self {
+            Self::East => Self::West,
+            Self::North => Self::South,
+            Self::South => Self::North,
+            Self::West => Self::East,
+        }
+    }
+
+    /// The direction 90° clockwise from this one.
+    pub fn next(self) -> Self {
+        match self {
+            Self::East => Self::South,
+            Self::North => Self::East,
+            Self::South => Self::West,
+            Self::West => Self::North,
+        }
+    }
+
+    pub fn xy(self) -> IVec2 {
+        match self {
+            Self::East => IVec2::X,
+            Self::North => IVec2::NEG_Y,
+            Self::South => IVec2::Y,
+            Self::West => IVec2::NEG_X,
+        }
+    }
+}
+
+impl<T> Index<usize> for Grid<T> {
+    type Output = Vec<T>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.rows.index(index)
+    }
+}
+
+impl<T> IndexMut<usize> for Grid<T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.rows.index_mut(index)
+    }
+}
+
+impl<T> Index<IVec2> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: IVec2) -> &Self::Output {
+        &self.rows[position.y as usize][position.x as usize]
+    }
+}
+
+impl<T> IndexMut<IVec2> for Grid<T> {
+    fn index_mut(&mut self, position: IVec2) -> &mut Self::Output {
+        &mut self.rows[position.y as usize][position.x as usize]
+    }
+}
+
+impl<T, I2: IntoIterator<Item = T>> FromIterator<I2> for Grid<T> {
+    fn from_iter<I1>(iter: I1) -> Self
+    where
+        I1: IntoIterator<Item = I2>,
+    {
+        let rows: Vec<Vec<T>> = iter
+            .into_iter()
+            .map(|cell_iter| cell_iter.into_iter().collect())
+            .collect();
+
+        assert!(!rows.is_empty(), "No rows in grid.");
+
+        let row_count = rows.len();
+        let col_count = rows[0].len();
+
+        assert!(
+            rows[1..].iter().all(|row| row.len() == col_count),
+            "Grid rows are not all of equal length."
+        );
+
+        Self {
+            col_count: col_count as i32,
+            rows,
+            row_count: row_count as i32,
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn col_count(&self) -> i32 {
+        self.col_count
+    }
+
+    pub fn row_count(&self) -> i32 {
+        self.row_count
+    }
+
+    pub fn rows(&self) -> &[Vec<T>] {
+        &self.rows
+    }
+
+    pub fn into_rows(self) -> Vec<Vec<T>> {
+        self.rows
+    }
+
+    pub fn in_bounds(&self, position: IVec2) -> bool {
+        position.min_element() >= 0 && position.x < self.col_count && position.y < self.row_count
+    }
+
+    pub fn get(&self, position: IVec2) -> Option<&T> {
+        self.in_bounds(position).then(|| &self[position])
+    }
+
+    pub fn get_mut(&mut self, position: IVec2) -> Option<&mut T> {
+        self.in_bounds(position).then(|| &mut self[position])
+    }
+
+    /// Iterates over every cell in reading order, alongside its position.
+    pub fn iter(&self) -> impl Iterator<Item = (IVec2, &T)> {
+        self.rows.iter().enumerate().flat_map(|(y, row)| {
+            let y = y as i32;
+
+            row.iter().enumerate().map(move |(x, cell)| {
+                let x = x as i32;
+
+                (IVec2 { x, y }, cell)
+            })
+        })
+    }
+
+    /// Iterates over every position in the grid in reading order.
+    pub fn positions(&self) -> impl Iterator<Item = IVec2> + '_ {
+        self.iter().map(|(position, _)| position)
+    }
+
+    /// The in-bounds positions orthogonally adjacent to `position`.
+    pub fn neighbors(&self, position: IVec2) -> impl Iterator<Item = IVec2> + '_ {
+        Direction::ALL
+            .into_iter()
+            .map(move |direction| position + direction.xy())
+            .filter(|&neighbor_position| self.in_bounds(neighbor_position))
+    }
+}
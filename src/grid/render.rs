@@ -0,0 +1,190 @@
+use std::{collections::HashSet, env, fmt};
+
+use glam::IVec2;
+
+use super::Grid;
+
+/// Environment variable which, when set to anything other than `0`, enables debug rendering.
+pub const DEBUG_VAR: &str = "AOC_DEBUG";
+
+pub fn debug_enabled() -> bool {
+    env::var(DEBUG_VAR).is_ok_and(|value| value != "0")
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Color {
+    Blue,
+    Cyan,
+    Dim,
+    Green,
+    Magenta,
+    Red,
+    Yellow,
+}
+
+impl Color {
+    fn ansi_code(self) -> &'static str {
+        match self {
+            Self::Blue => "\x1b[1;34m",
+            Self::Cyan => "\x1b[1;36m",
+            Self::Dim => "\x1b[2m",
+            Self::Green => "\x1b[1;32m",
+            Self::Magenta => "\x1b[1;35m",
+            Self::Red => "\x1b[1;31m",
+            Self::Yellow => "\x1b[1;33m",
+        }
+    }
+}
+
+const COLOR_RESET: &str = "\x1b[0m";
+
+/// A set of positions to draw over the top of the grid, e.g. a path or some cells of interest.
+struct Overlay {
+    color: Option<Color>,
+    marker: Option<char>,
+    positions: HashSet<IVec2>,
+}
+
+/// Renders a grid as text, one char per cell.
+///
+/// Overlays are drawn in the order they were added, so later overlays take priority over earlier
+/// ones where they overlap.
+pub struct Renderer<'a, T> {
+    background: Option<Color>,
+    cell_char: Box<dyn Fn(&T) -> char + 'a>,
+    color: bool,
+    overlays: Vec<Overlay>,
+    rows: &'a [Vec<T>],
+    rulers: bool,
+}
+
+impl<T> Grid<T> {
+    pub fn render<'a>(&'a self, cell_char: impl Fn(&T) -> char + 'a) -> Renderer<'a, T> {
+        Renderer::new(self.rows(), cell_char)
+    }
+}
+
+impl<'a, T> Renderer<'a, T> {
+    /// Creates a renderer for a grid which isn't stored as a [`Grid`].
+    pub fn new(rows: &'a [Vec<T>], cell_char: impl Fn(&T) -> char + 'a) -> Self {
+        Self {
+            background: None,
+            cell_char: Box::new(cell_char),
+            color: true,
+            overlays: Vec::new(),
+            rows,
+            rulers: false,
+        }
+    }
+
+    /// Colors every cell which isn't covered by an overlay, e.g. [`Color::Dim`] to make overlays
+    /// stand out.
+    pub fn background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    /// Enables or disables ANSI colors. Colors are enabled by default.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Colors the cells at `positions`, keeping their usual chars.
+    pub fn highlight(mut self, positions: impl IntoIterator<Item = IVec2>, color: Color) -> Self {
+        self.overlays.push(Overlay {
+            color: Some(color),
+            marker: None,
+            positions: positions.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Replaces the cells at `positions` with `marker`.
+    pub fn mark(mut self, positions: impl IntoIterator<Item = IVec2>, marker: char) -> Self {
+        self.overlays.push(Overlay {
+            color: None,
+            marker: Some(marker),
+            positions: positions.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Replaces the cells at `positions` with `marker`, drawn in `color`.
+    pub fn mark_colored(
+        mut self,
+        positions: impl IntoIterator<Item = IVec2>,
+        marker: char,
+        color: Color,
+    ) -> Self {
+        self.overlays.push(Overlay {
+            color: Some(color),
+            marker: Some(marker),
+            positions: positions.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Adds the last digit of each column and row number around the edge of the grid.
+    pub fn rulers(mut self, rulers: bool) -> Self {
+        self.rulers = rulers;
+        self
+    }
+
+    /// Prints the grid to stderr, but only when debug rendering is enabled via [`DEBUG_VAR`].
+    pub fn debug_print(&self) {
+        if debug_enabled() {
+            eprintln!("{self}");
+        }
+    }
+}
+
+impl<T> fmt::Display for Renderer<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let col_count = self.rows.first().map_or(0, Vec::len);
+        let ruler_width = self.rows.len().saturating_sub(1).to_string().len();
+
+        if self.rulers {
+            write!(f, "{:ruler_width$} ", "")?;
+
+            for x in 0..col_count {
+                write!(f, "{}", x % 10)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        for (y, row) in self.rows.iter().enumerate() {
+            if self.rulers {
+                write!(f, "{y:>ruler_width$} ")?;
+            }
+
+            for (x, cell) in row.iter().enumerate() {
+                let position = IVec2::new(x as i32, y as i32);
+
+                let mut char = (self.cell_char)(cell);
+                let mut color = self.background;
+
+                for overlay in &self.overlays {
+                    if overlay.positions.contains(&position) {
+                        char = overlay.marker.unwrap_or(char);
+                        color = overlay.color;
+                    }
+                }
+
+                match color {
+                    Some(color) if self.color => {
+                        write!(f, "{}{char}{COLOR_RESET}", color.ansi_code())?
+                    }
+                    _ => write!(f, "{char}")?,
+                }
+            }
+
+            if y + 1 < self.rows.len() {
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use glam::IVec2;
+
+use super::{Direction, Grid};
+
+/// A connected set of positions within a grid, as found by [`Grid::flood_fill`].
+pub struct Region {
+    perimeter: u64,
+    positions: HashSet<IVec2>,
+    sides: u64,
+}
+
+impl Region {
+    fn new(positions: HashSet<IVec2>) -> Self {
+        let mut perimeter = 0;
+        let mut sides = 0;
+
+        for &position in &positions {
+            for direction in Direction::ALL {
+                if !positions.contains(&(position + direction.xy())) {
+                    perimeter += 1;
+                }
+
+                // Each corner of the region's boundary begins a new side, so counting corners
+                // counts sides. A corner is either convex (neither orthogonal neighbor is in the
+                // region) or concave (both are, but the diagonal between them isn't).
+                let clockwise = direction.next();
+
+                let orthogonal_a = positions.contains(&(position + direction.xy()));
+                let orthogonal_b = positions.contains(&(position + clockwise.xy()));
+                let diagonal = positions.contains(&(position + direction.xy() + clockwise.xy()));
+
+                if (!orthogonal_a && !orthogonal_b) || (orthogonal_a && orthogonal_b && !diagonal) {
+                    sides += 1;
+                }
+            }
+        }
+
+        Self {
+            perimeter,
+            positions,
+            sides,
+        }
+    }
+
+    pub fn area(&self) -> u64 {
+        self.positions.len() as u64
+    }
+
+    pub fn contains(&self, position: IVec2) -> bool {
+        self.positions.contains(&position)
+    }
+
+    /// The number of unit edges separating this region from everything outside it.
+    pub fn perimeter(&self) -> u64 {
+        self.perimeter
+    }
+
+    pub fn positions(&self) -> &HashSet<IVec2> {
+        &self.positions
+    }
+
+    /// The number of straight fence sections needed to enclose this region.
+    pub fn sides(&self) -> u64 {
+        self.sides
+    }
+}
+
+/// Search functions over the grid. Each takes a `can_step` predicate which is given the cell being
+/// stepped from and the cell being stepped to, and returns whether that step is allowed. Only
+/// orthogonal steps are considered.
+impl<T> Grid<T> {
+    /// Returns the minimum number of steps needed to reach every position reachable from `start`.
+    pub fn bfs_distances(
+        &self,
+        start: IVec2,
+        mut can_step: impl FnMut(&T, &T) -> bool,
+    ) -> HashMap<IVec2, u64> {
+        let mut distances: HashMap<IVec2, u64> = HashMap::from_iter([(start, 0)]);
+        let mut search_queue: VecDeque<IVec2> = VecDeque::from_iter([start]);
+
+        while let Some(position) = search_queue.pop_front() {
+            let distance = distances[&position];
+
+            for neighbor_position in self.neighbors(position) {
+                if distances.contains_key(&neighbor_position)
+                    || !can_step(&self[position], &self[neighbor_position])
+                {
+                    continue;
+                }
+
+                distances.insert(neighbor_position, distance + 1);
+                search_queue.push_back(neighbor_position);
+            }
+        }
+
+        distances
+    }
+
+    /// Returns every position reachable from `start`, including `start` itself.
+    pub fn dfs_reachable(
+        &self,
+        start: IVec2,
+        mut can_step: impl FnMut(&T, &T) -> bool,
+    ) -> HashSet<IVec2> {
+        let mut visited_positions: HashSet<IVec2> = HashSet::from_iter([start]);
+        let mut search_stack: Vec<IVec2> = vec![start];
+
+        while let Some(position) = search_stack.pop() {
+            for neighbor_position in self.neighbors(position) {
+                if !visited_positions.contains(&neighbor_position)
+                    && can_step(&self[position], &self[neighbor_position])
+                {
+                    visited_positions.insert(neighbor_position);
+                    search_stack.push(neighbor_position);
+                }
+            }
+        }
+
+        visited_positions
+    }
+
+    /// Returns the region containing `start`, where `can_step` decides whether two adjacent cells
+    /// belong to the same region.
+    pub fn flood_fill(&self, start: IVec2, can_step: impl FnMut(&T, &T) -> bool) -> Region {
+        Region::new(self.dfs_reachable(start, can_step))
+    }
+
+    /// Partitions the grid into its connected components, in reading order of each region's first
+    /// position.
+    pub fn regions(&self, mut can_step: impl FnMut(&T, &T) -> bool) -> Vec<Region> {
+        let mut visited_positions: HashSet<IVec2> =
+            HashSet::with_capacity(self.col_count as usize * self.row_count as usize);
+
+        let mut regions = Vec::new();
+
+        for position in self.positions() {
+            if visited_positions.contains(&position) {
+                continue;
+            }
+
+            let region = self.flood_fill(position, &mut can_step);
+            visited_positions.extend(region.positions());
+
+            regions.push(region);
+        }
+
+        regions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &str) -> Grid<char> {
+        rows.lines().map(str::chars).collect()
+    }
+
+    /// The area, perimeter and number of sides of each region of like chars, in reading order.
+    fn region_measurements(rows: &str) -> Vec<(u64, u64, u64)> {
+        grid(rows)
+            .regions(|char, neighbor_char| char == neighbor_char)
+            .iter()
+            .map(|region| (region.area(), region.perimeter(), region.sides()))
+            .collect()
+    }
+
+    #[test]
+    fn bfs_distances_follow_the_maze() {
+        let maze = grid(".#.\n.#.\n...");
+
+        let distances = maze.bfs_distances(IVec2::ZERO, |_, &to| to != '#');
+
+        let expected_distances: HashMap<IVec2, u64> = [
+            ((0, 0), 0),
+            ((0, 1), 1),
+            ((0, 2), 2),
+            ((1, 2), 3),
+            ((2, 2), 4),
+            ((2, 1), 5),
+            ((2, 0), 6),
+        ]
+        .into_iter()
+        .map(|((x, y), distance)| (IVec2::new(x, y), distance))
+        .collect();
+
+        assert_eq!(distances, expected_distances);
+    }
+
+    #[test]
+    fn flood_fill_stays_within_its_region() {
+        let garden = grid("OOO\nOXO\nOOO");
+
+        let inner = garden.flood_fill(IVec2::ONE, |char, neighbor_char| char == neighbor_char);
+        let outer = garden.flood_fill(IVec2::ZERO, |char, neighbor_char| char == neighbor_char);
+
+        assert_eq!((inner.area(), inner.perimeter(), inner.sides()), (1, 4, 4));
+        assert_eq!((outer.area(), outer.perimeter(), outer.sides()), (8, 16, 8));
+        assert!(!outer.contains(IVec2::ONE));
+    }
+
+    #[test]
+    fn regions_are_measured_like_the_puzzle_examples() {
+        assert_eq!(
+            region_measurements("AAAA\nBBCD\nBBCC\nEEEC"),
+            [(4, 10, 4), (4, 8, 4), (4, 10, 8), (1, 4, 4), (3, 8, 4)]
+        );
+
+        // The holes in a region add to its sides as well as its perimeter.
+        assert_eq!(
+            region_measurements("OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO"),
+            [(21, 36, 20), (1, 4, 4), (1, 4, 4), (1, 4, 4), (1, 4, 4)]
+        );
+
+        assert_eq!(
+            region_measurements("EEEEE\nEXXXX\nEEEEE\nEXXXX\nEEEEE"),
+            [(17, 36, 12), (4, 10, 4), (4, 10, 4)]
+        );
+
+        // Regions touching only at a corner are counted as separate sides.
+        let total_price: u64 =
+            region_measurements("AAAAAA\nAAABBA\nAAABBA\nABBAAA\nABBAAA\nAAAAAA")
+                .into_iter()
+                .map(|(area, _, sides)| area * sides)
+                .sum();
+
+        assert_eq!(total_price, 368);
+    }
+}
@@ -1,10 +1,25 @@
-use std::{borrow::Cow, fs, path::Path};
+use std::{
+    borrow::Cow,
+    env, fs,
+    path::Path,
+    sync::{Mutex, PoisonError},
+};
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
+
+use report::{Format, Table};
+
+pub mod grid;
+pub mod parse;
+pub mod pathfinding;
+pub mod report;
 
 pub const INITIALS: [&str; 5] = ["xmp", "jwc", "scb", "slh", "tmf"];
 const COLUMN_PADDING: usize = 3;
 
+/// The variables read by [`variant_var`] which were set, along with their values.
+static VARIANTS: Mutex<Vec<(&str, String)>> = Mutex::new(Vec::new());
+
 fn day(source_file: &str) -> anyhow::Result<Cow<'_, str>> {
     Ok(Path::new(source_file)
         .file_stem()
@@ -12,6 +27,175 @@ fn day(source_file: &str) -> anyhow::Result<Cow<'_, str>> {
         .to_string_lossy())
 }
 
+fn read_input(day: &str, initials: &str) -> anyhow::Result<String> {
+    let input = fs::read_to_string(format!("input/{day}/{initials}.txt"))?;
+
+    Ok(parse::normalize(&input))
+}
+
+/// Reads an environment variable which makes a day solve a variant of the puzzle rather than the
+/// puzzle itself. If it's set, the printed answers or diagnostics are flagged as being for the
+/// variant, so that a leftover variable can't go unnoticed.
+pub fn variant_var(name: &'static str) -> Option<String> {
+    let value = env::var(name).ok()?;
+
+    let mut variants = VARIANTS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    if !variants
+        .iter()
+        .any(|&(variant_name, _)| variant_name == name)
+    {
+        variants.push((name, value.clone()));
+    }
+
+    Some(value)
+}
+
+/// Reads an environment variable which selects one of the named `choices`, defaulting to the first
+/// if it's unset. The `description` names the setting in the error for an unknown value.
+pub fn env_choice<T: Copy>(
+    name: &str,
+    description: &str,
+    choices: &[(&str, T)],
+) -> anyhow::Result<T> {
+    let Ok(value) = env::var(name) else {
+        return choices
+            .first()
+            .map(|&(_, choice)| choice)
+            .ok_or(anyhow!("`{name}` should have at least one choice."));
+    };
+
+    if let Some(&(_, choice)) = choices
+        .iter()
+        .find(|&&(choice_name, _)| choice_name == value)
+    {
+        return Ok(choice);
+    }
+
+    let names: Vec<_> = choices
+        .iter()
+        .map(|(choice_name, _)| format!("`{choice_name}`"))
+        .collect();
+
+    let names = match names.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {last}", rest.join(", ")),
+        None => String::new(),
+    };
+
+    bail!("{description} `{value}` should be {names}.")
+}
+
+/// Prints every variable read by [`variant_var`] which was set. This goes to stderr, so it
+/// doesn't end up in any CSV output.
+fn print_variants() {
+    for (name, value) in VARIANTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+    {
+        eprintln!("\x1b[1;33mVariant:\x1b[0m {name}={value}");
+    }
+}
+
+/// Command line options for a day's diagnostic output:
+/// * `--diagnose` prints diagnostic tables instead of the usual answers.
+/// * `--csv` writes those tables as CSV, separated by blank lines.
+/// * `--initials <initials>` only diagnoses the given participant's input.
+struct DiagnosticOptions {
+    format: Format,
+    initials: Option<String>,
+}
+
+impl DiagnosticOptions {
+    fn from_args() -> anyhow::Result<Option<Self>> {
+        let mut diagnose = false;
+        let mut options = Self {
+            format: Format::Table,
+            initials: None,
+        };
+
+        let mut args = env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--diagnose" => diagnose = true,
+                "--csv" => options.format = Format::Csv,
+                "--initials" => {
+                    let initials = args
+                        .next()
+                        .ok_or(anyhow!("`--initials` requires a value."))?
+                        .to_lowercase();
+
+                    if !INITIALS.contains(&initials.as_str()) {
+                        bail!("Unknown initials `{initials}`.");
+                    }
+
+                    options.initials = Some(initials);
+                }
+                _ => bail!("Unknown argument `{arg}`."),
+            }
+        }
+
+        // Concatenating every participant's tables wouldn't produce usable CSV.
+        if options.format == Format::Csv && options.initials.is_none() {
+            bail!("`--csv` requires `--initials` to select a single input.");
+        }
+
+        Ok(diagnose.then_some(options))
+    }
+}
+
+/// Whether `--diagnose` was passed on the command line along with `--initials`, so diagnostics
+/// are only produced for a single participant's input.
+pub fn diagnosing_single_input() -> bool {
+    DiagnosticOptions::from_args()
+        .is_ok_and(|options| options.is_some_and(|options| options.initials.is_some()))
+}
+
+/// As [`solve_day`], unless `--diagnose` is passed on the command line, in which case the tables
+/// returned by `diagnose` are printed for each participant's input instead.
+pub fn solve_or_diagnose_day(
+    source_file: &str,
+    part1: fn(&str) -> anyhow::Result<u64>,
+    part2: fn(&str) -> anyhow::Result<u64>,
+    diagnose: fn(&str) -> anyhow::Result<Vec<Table>>,
+) -> anyhow::Result<()> {
+    let Some(options) = DiagnosticOptions::from_args()? else {
+        return solve_day(source_file, part1, part2);
+    };
+
+    let day = day(source_file)?;
+
+    for initials in INITIALS {
+        if options
+            .initials
+            .as_ref()
+            .is_some_and(|selected_initials| selected_initials != initials)
+        {
+            continue;
+        }
+
+        let input = read_input(&day, initials)?;
+
+        if input.is_empty() {
+            continue;
+        }
+
+        if options.format == Format::Table {
+            println!("══ {} ══", initials.to_uppercase());
+        }
+
+        for table in diagnose(&input)? {
+            println!("{}", table.render(options.format));
+        }
+    }
+
+    print_variants();
+
+    Ok(())
+}
+
 pub fn solve_day(
     source_file: &str,
     part1: fn(&str) -> anyhow::Result<u64>,
@@ -28,7 +212,7 @@ pub fn solve_day(
     let mut answers = Vec::with_capacity(5);
 
     for initials in INITIALS {
-        let input = fs::read_to_string(format!("input/{day}/{initials}.txt"))?;
+        let input = read_input(&day, initials)?;
 
         if input.is_empty() {
             answers.push((Err(anyhow!("Empty input")), Err(anyhow!("Empty input"))));
@@ -128,6 +312,8 @@ pub fn solve_day(
         "", "", "", ""
     );
 
+    print_variants();
+
     Ok(())
 }
 
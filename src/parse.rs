@@ -0,0 +1,563 @@
+use std::{
+    error::Error,
+    fmt,
+    ops::{Deref, RangeInclusive},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, ensure};
+use glam::IVec2;
+
+use crate::grid::Grid;
+
+/// The most input shown alongside a parse error.
+const SNIPPET_LENGTH: usize = 40;
+
+/// An error encountered while parsing puzzle input, which records where in the input it occurred.
+#[derive(Debug)]
+pub struct ParseError {
+    /// 1-based char column, if the error can be attributed to a specific part of the line.
+    column: Option<usize>,
+    /// 1-based line number within the whole input.
+    line: usize,
+    message: String,
+    snippet: String,
+}
+
+impl ParseError {
+    fn new(line: &Line<'_>, column: Option<usize>, message: impl fmt::Display) -> Self {
+        let char_count = line.text.chars().count();
+
+        let snippet_start = match column {
+            Some(column) if char_count > SNIPPET_LENGTH => (column - 1)
+                .saturating_sub(SNIPPET_LENGTH / 2)
+                .min(char_count - SNIPPET_LENGTH),
+            _ => 0,
+        };
+
+        let mut snippet: String = line
+            .text
+            .chars()
+            .skip(snippet_start)
+            .take(SNIPPET_LENGTH)
+            .collect();
+
+        if snippet_start > 0 {
+            snippet.insert_str(0, "...");
+        }
+
+        if snippet_start + SNIPPET_LENGTH < char_count {
+            snippet.push_str("...");
+        }
+
+        Self {
+            column,
+            line: line.number,
+            message: message.to_string(),
+            snippet,
+        }
+    }
+
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (line {}", self.message, self.line)?;
+
+        if let Some(column) = self.column {
+            write!(f, ", column {column}")?;
+        }
+
+        write!(f, ": `{}`)", self.snippet)
+    }
+}
+
+impl Error for ParseError {}
+
+/// A single line of input, which knows its own position so that errors can point back to it.
+///
+/// Derefs to the line's text, so the usual `str` methods can be used to pick it apart.
+#[derive(Clone, Copy, Debug)]
+pub struct Line<'a> {
+    /// 1-based line number within the whole input.
+    number: usize,
+    text: &'a str,
+}
+
+impl Deref for Line<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.text
+    }
+}
+
+impl<'a> Line<'a> {
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// An error concerning the line as a whole.
+    pub fn error(&self, message: impl fmt::Display) -> ParseError {
+        ParseError::new(self, None, message)
+    }
+
+    /// An error concerning the char at the 0-based char index `column`.
+    pub fn error_at(&self, column: usize, message: impl fmt::Display) -> ParseError {
+        ParseError::new(self, Some(column + 1), message)
+    }
+
+    /// An error concerning `part`, which should be a substring of this line (e.g. as returned by
+    /// `split`).
+    pub fn error_in(&self, part: &str, message: impl fmt::Display) -> ParseError {
+        match self.column_of(part) {
+            Some(column) => self.error_at(column, message),
+            None => self.error(message),
+        }
+    }
+
+    /// Parses the whole line, attaching the line's position to any error.
+    pub fn parse<T>(&self) -> Result<T, ParseError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.text.parse().map_err(|err| self.error(err))
+    }
+
+    /// Parses `part`, a substring of this line, attaching its position to any error.
+    pub fn parse_part<T>(&self, part: &str) -> Result<T, ParseError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let part = part.trim();
+
+        part.parse()
+            .map_err(|err| self.error_in(part, format!("Couldn't parse `{part}`: {err}")))
+    }
+
+    /// Splits the line on `separator` and parses every non-empty field.
+    pub fn parse_split<T>(&self, separator: char) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.text
+            .split(separator)
+            .filter(|field| !field.trim().is_empty())
+            .map(|field| self.parse_part(field))
+            .collect()
+    }
+
+    /// Parses every run of digits in the line, each including a directly preceding `-` if there is
+    /// one, ignoring all other text.
+    pub fn integers<T>(&self) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        integer_parts(self.text)
+            .map(|part| self.parse_part(part))
+            .collect()
+    }
+
+    /// Parses exactly as many integers as `I` holds, e.g. `[u64; 2]` or `(u64, i32)`, in the
+    /// manner of [`Line::integers`]. It's an error for the line to contain more or fewer integers.
+    pub fn extract<I: Integers>(&self) -> Result<I, ParseError> {
+        let parts: Vec<(Line, &str)> = integer_parts(self.text).map(|part| (*self, part)).collect();
+
+        if parts.len() != I::COUNT {
+            return Err(self.error(format!(
+                "Expected {} integers, but found {}.",
+                I::COUNT,
+                parts.len()
+            )));
+        }
+
+        I::from_parts(&parts)
+    }
+
+    /// The 0-based char column at which `part` begins, if it is a substring of this line.
+    fn column_of(&self, part: &str) -> Option<usize> {
+        let offset = (part.as_ptr() as usize).checked_sub(self.text.as_ptr() as usize)?;
+
+        (offset + part.len() <= self.text.len()).then(|| self.text[..offset].chars().count())
+    }
+}
+
+/// Iterates over every integer-looking substring of `text`.
+fn integer_parts(text: &str) -> impl Iterator<Item = &str> {
+    let bytes = text.as_bytes();
+    let mut index = 0;
+
+    std::iter::from_fn(move || {
+        while index < bytes.len() && !bytes[index].is_ascii_digit() {
+            index += 1;
+        }
+
+        if index == bytes.len() {
+            return None;
+        }
+
+        let start = if index > 0 && bytes[index - 1] == b'-' {
+            index - 1
+        } else {
+            index
+        };
+
+        while index < bytes.len() && bytes[index].is_ascii_digit() {
+            index += 1;
+        }
+
+        Some(&text[start..index])
+    })
+}
+
+/// A blank-line separated chunk of input.
+#[derive(Clone, Copy, Debug)]
+pub struct Section<'a> {
+    /// 1-based line number of the section's first line within the whole input.
+    first_line: usize,
+    text: &'a str,
+}
+
+impl<'a> Section<'a> {
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// An error concerning the section as a whole, reported at its first line.
+    pub fn error(&self, message: impl fmt::Display) -> ParseError {
+        self.lines()
+            .next()
+            .unwrap_or(Line {
+                number: self.first_line,
+                text: "",
+            })
+            .error(message)
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = Line<'a>> {
+        let first_line = self.first_line;
+
+        self.text
+            .lines()
+            .enumerate()
+            .map(move |(index, text)| Line {
+                number: first_line + index,
+                text,
+            })
+    }
+
+    /// Reads the section as a grid. See [`grid`].
+    pub fn grid<T, E>(
+        &self,
+        cell: impl FnMut(IVec2, char) -> Result<T, E>,
+    ) -> Result<Grid<T>, ParseError>
+    where
+        E: fmt::Display,
+    {
+        grid(self.lines(), cell)
+    }
+
+    /// As [`Line::integers`], across every line of the section.
+    pub fn integers<T>(&self) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.lines()
+            .flat_map(|line| integer_parts(line.text).map(move |part| line.parse_part(part)))
+            .collect()
+    }
+
+    /// As [`Line::extract`], across every line of the section.
+    pub fn extract<I: Integers>(&self) -> Result<I, ParseError> {
+        let parts: Vec<(Line, &str)> = self
+            .lines()
+            .flat_map(|line| integer_parts(line.text).map(move |part| (line, part)))
+            .collect();
+
+        if parts.len() != I::COUNT {
+            return Err(self.error(format!(
+                "Expected {} integers, but found {}.",
+                I::COUNT,
+                parts.len()
+            )));
+        }
+
+        I::from_parts(&parts)
+    }
+}
+
+/// A fixed number of integers, which can be pulled out of some text with [`Line::extract`] or
+/// [`Section::extract`].
+pub trait Integers: Sized {
+    const COUNT: usize;
+
+    /// Parses exactly `COUNT` integer substrings, each paired with the line it came from.
+    fn from_parts(parts: &[(Line<'_>, &str)]) -> Result<Self, ParseError>;
+}
+
+impl<T, const N: usize> Integers for [T; N]
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    const COUNT: usize = N;
+
+    fn from_parts(parts: &[(Line<'_>, &str)]) -> Result<Self, ParseError> {
+        let integers: Vec<T> = parts
+            .iter()
+            .map(|(line, part)| line.parse_part(part))
+            .collect::<Result<_, _>>()?;
+
+        let Ok(integers) = integers.try_into() else {
+            unreachable!("Callers should always pass exactly `N` parts.");
+        };
+
+        Ok(integers)
+    }
+}
+
+macro_rules! impl_integers_for_tuple {
+    ($count:literal; $($type:ident $index:tt),+) => {
+        impl<$($type),+> Integers for ($($type,)+)
+        where
+            $($type: FromStr, $type::Err: fmt::Display,)+
+        {
+            const COUNT: usize = $count;
+
+            fn from_parts(parts: &[(Line<'_>, &str)]) -> Result<Self, ParseError> {
+                Ok(($({
+                    let (line, part) = parts[$index];
+                    line.parse_part::<$type>(part)?
+                },)+))
+            }
+        }
+    };
+}
+
+impl_integers_for_tuple!(1; A 0);
+impl_integers_for_tuple!(2; A 0, B 1);
+impl_integers_for_tuple!(3; A 0, B 1, C 2);
+impl_integers_for_tuple!(4; A 0, B 1, C 2, D 3);
+impl_integers_for_tuple!(5; A 0, B 1, C 2, D 3, E 4);
+impl_integers_for_tuple!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// Iterates over the lines of `input`, numbered from 1.
+pub fn lines(input: &str) -> impl Iterator<Item = Line<'_>> {
+    Section {
+        first_line: 1,
+        text: input,
+    }
+    .lines()
+}
+
+/// Splits `input` into its sections, which are separated by one or more blank (or
+/// whitespace-only) lines. Blank lines at the start or end of the input are ignored.
+pub fn sections(input: &str) -> impl Iterator<Item = Section<'_>> {
+    let mut lines = input.split_inclusive('\n').enumerate().peekable();
+    let mut offset = 0;
+
+    std::iter::from_fn(move || {
+        // Skip the blank lines before the next section
+        while let Some((_, line)) = lines.next_if(|(_, line)| line.trim().is_empty()) {
+            offset += line.len();
+        }
+
+        let &(index, _) = lines.peek()?;
+        let start = offset;
+
+        while let Some((_, line)) = lines.next_if(|(_, line)| !line.trim().is_empty()) {
+            offset += line.len();
+        }
+
+        Some(Section {
+            first_line: index + 1,
+            text: input[start..offset].trim_end_matches(['\r', '\n']),
+        })
+    })
+}
+
+/// Cleans up formatting differences which can creep into puzzle input when it's saved, so that
+/// solvers only ever see `\n` line endings, with no trailing whitespace on any line, no blank
+/// lines at the end of the input, and no byte order mark.
+pub fn normalize(input: &str) -> String {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+
+    let mut normalized = String::with_capacity(input.len());
+
+    for line in input.lines() {
+        normalized.push_str(line.trim_end());
+        normalized.push('\n');
+    }
+
+    normalized.truncate(normalized.trim_end().len());
+
+    normalized
+}
+
+/// Splits `input` into exactly `N` sections, or returns an error saying how many were expected.
+pub fn split_sections<const N: usize>(input: &str) -> Result<[Section<'_>; N], ParseError> {
+    let sections: Vec<Section> = sections(input).collect();
+    let section_count = sections.len();
+
+    sections.try_into().map_err(|_| {
+        let line = Line {
+            number: 1,
+            text: input.lines().next().unwrap_or_default(),
+        };
+
+        line.error(format!(
+            "Expected input to have {N} sections, but found {section_count}."
+        ))
+    })
+}
+
+/// Reads `lines` as a rectangular grid, converting each char with `cell`, which is also passed
+/// the char's position. Errors from `cell`, and rows of differing lengths, are reported with their
+/// position in the input.
+pub fn grid<'a, T, E>(
+    lines: impl IntoIterator<Item = Line<'a>>,
+    mut cell: impl FnMut(IVec2, char) -> Result<T, E>,
+) -> Result<Grid<T>, ParseError>
+where
+    E: fmt::Display,
+{
+    let mut rows: Vec<Vec<T>> = Vec::new();
+    let mut col_count: Option<usize> = None;
+
+    for (y, line) in lines.into_iter().enumerate() {
+        let y = y as i32;
+
+        let row = line
+            .chars()
+            .enumerate()
+            .map(|(x, char)| {
+                cell(IVec2::new(x as i32, y), char).map_err(|err| line.error_at(x, err))
+            })
+            .collect::<Result<Vec<T>, ParseError>>()?;
+
+        match col_count {
+            None => col_count = Some(row.len()),
+            Some(col_count) if col_count != row.len() => {
+                return Err(line.error(format!(
+                    "Grid row has {} cells, but the first row has {col_count}.",
+                    row.len()
+                )));
+            }
+            Some(_) => {}
+        }
+
+        rows.push(row);
+    }
+
+    if rows.is_empty() {
+        return Err(Line {
+            number: 1,
+            text: "",
+        }
+        .error("Grid has no rows."));
+    }
+
+    Ok(rows.into_iter().collect())
+}
+
+/// A `name=value` setting from a comma separated list such as `steps=1-3,equal=true`, which is
+/// how days take options from environment variables.
+#[derive(Clone, Copy, Debug)]
+pub struct Setting<'a> {
+    name: &'a str,
+    value: &'a str,
+}
+
+impl<'a> Setting<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn value(&self) -> &'a str {
+        self.value
+    }
+
+    /// Parses the setting's value.
+    pub fn parse<T>(&self) -> anyhow::Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.value.parse().map_err(|err| {
+            anyhow!(
+                "Couldn't parse `{}` for setting `{}`: {err}",
+                self.value,
+                self.name
+            )
+        })
+    }
+
+    /// Parses the setting's value as a range. See [`range`].
+    pub fn range<T>(&self) -> anyhow::Result<RangeInclusive<T>>
+    where
+        T: FromStr + PartialOrd,
+        T::Err: fmt::Display,
+    {
+        range(self.value).map_err(|err| anyhow!("Invalid setting `{}`: {err}", self.name))
+    }
+}
+
+/// Parses a non-empty inclusive range, written either `min-max`, e.g. `1-3`, or as a single value
+/// for a range containing only that value.
+pub fn range<T>(text: &str) -> anyhow::Result<RangeInclusive<T>>
+where
+    T: FromStr + PartialOrd,
+    T::Err: fmt::Display,
+{
+    let bound = |bound: &str| {
+        let bound = bound.trim();
+
+        bound
+            .parse::<T>()
+            .map_err(|err| anyhow!("Couldn't parse `{bound}`: {err}"))
+    };
+
+    let (min, max) = text.split_once('-').unwrap_or((text, text));
+    let range = bound(min)?..=bound(max)?;
+
+    ensure!(!range.is_empty(), "Range `{text}` should not be empty.");
+
+    Ok(range)
+}
+
+/// Splits a comma separated list of `name=value` settings, ignoring empty entries.
+pub fn settings(text: &str) -> impl Iterator<Item = anyhow::Result<Setting<'_>>> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|setting| !setting.is_empty())
+        .map(|setting| {
+            let (name, value) = setting.split_once('=').ok_or(anyhow!(
+                "Setting `{setting}` should be of the form `name=value`."
+            ))?;
+
+            Ok(Setting {
+                name: name.trim(),
+                value: value.trim(),
+            })
+        })
+}
@@ -0,0 +1,311 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
+    hash::Hash,
+};
+
+/// The result of a shortest path search from a single start state.
+///
+/// Only states which were settled by the search are recorded, so when the search stopped early at
+/// a goal, states further away than the goal won't have a distance.
+pub struct ShortestPaths<S> {
+    cost: Option<u64>,
+    distances: HashMap<S, u64>,
+    goals: Vec<S>,
+    predecessors: HashMap<S, Vec<S>>,
+    start: S,
+}
+
+impl<S: Clone + Eq + Hash> ShortestPaths<S> {
+    /// The optimal cost of reaching a goal, if any goal was reachable.
+    pub fn cost(&self) -> Option<u64> {
+        self.cost
+    }
+
+    pub fn distance(&self, state: &S) -> Option<u64> {
+        self.distances.get(state).copied()
+    }
+
+    pub fn distances(&self) -> &HashMap<S, u64> {
+        &self.distances
+    }
+
+    /// Every goal state reachable at the optimal cost.
+    pub fn goals(&self) -> &[S] {
+        &self.goals
+    }
+
+    /// Every state on any optimal path between the start and a goal, including both endpoints.
+    pub fn optimal_states(&self) -> HashSet<S> {
+        let mut optimal_states: HashSet<S> = self.goals.iter().cloned().collect();
+        let mut search_stack: Vec<&S> = self.goals.iter().collect();
+
+        while let Some(state) = search_stack.pop() {
+            for predecessor in self.predecessors(state) {
+                if optimal_states.insert(predecessor.clone()) {
+                    search_stack.push(predecessor);
+                }
+            }
+        }
+
+        optimal_states
+    }
+
+    /// One optimal path from the start to the first goal found.
+    pub fn path(&self) -> Option<Vec<S>> {
+        self.path_to(self.goals.first()?)
+    }
+
+    /// One optimal path from the start to `state`, including both endpoints.
+    pub fn path_to(&self, state: &S) -> Option<Vec<S>> {
+        if !self.distances.contains_key(state) {
+            return None;
+        }
+
+        let mut path = vec![state.clone()];
+        let mut current_state = state;
+
+        while *current_state != self.start {
+            current_state = self.predecessors(current_state).first()?;
+            path.push(current_state.clone());
+        }
+
+        path.reverse();
+
+        Some(path)
+    }
+
+    /// Every state from which `state` can be reached along an optimal path with a single step.
+    pub fn predecessors(&self, state: &S) -> &[S] {
+        self.predecessors
+            .get(state)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// Finds the cheapest paths from `start` to any state satisfying `is_goal`.
+///
+/// `successors` yields each state reachable in one step along with the (non-negative) cost of that
+/// step. To find distances to every reachable state, pass an `is_goal` which always returns `false`.
+pub fn dijkstra<S, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> ShortestPaths<S>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = (S, u64)>,
+{
+    astar(start, successors, |_| 0, is_goal)
+}
+
+/// As [`dijkstra`], but guided towards a goal by `heuristic`, an estimate of the remaining cost.
+///
+/// The heuristic must never overestimate, and must be consistent (it may not decrease by more
+/// than the cost of any step) for the recorded predecessors to include every optimal path.
+pub fn astar<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> u64,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> ShortestPaths<S>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = (S, u64)>,
+{
+    struct QueuedState<S> {
+        distance: u64,
+        priority: Reverse<u64>,
+        state: S,
+    }
+
+    impl<S> Eq for QueuedState<S> {}
+
+    impl<S> Ord for QueuedState<S> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.priority.cmp(&other.priority)
+        }
+    }
+
+    impl<S> PartialEq for QueuedState<S> {
+        fn eq(&self, other: &Self) -> bool {
+            self.priority == other.priority
+        }
+    }
+
+    impl<S> PartialOrd for QueuedState<S> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    let mut distances: HashMap<S, u64> = HashMap::from_iter([(start.clone(), 0)]);
+    let mut predecessors: HashMap<S, Vec<S>> = HashMap::new();
+    let mut settled_states: HashSet<S> = HashSet::new();
+
+    let mut cost: Option<u64> = None;
+    let mut goals: Vec<S> = Vec::new();
+
+    let mut visit_next: BinaryHeap<QueuedState<S>> = BinaryHeap::new();
+
+    visit_next.push(QueuedState {
+        distance: 0,
+        priority: Reverse(heuristic(&start)),
+        state: start.clone(),
+    });
+
+    while let Some(QueuedState {
+        distance,
+        priority: Reverse(priority),
+        state,
+    }) = visit_next.pop()
+    {
+        // Once every remaining state is further away than the goal, no more optimal paths exist.
+        if cost.is_some_and(|cost| priority > cost) {
+            break;
+        }
+
+        // A cheaper way to reach this state was queued after this one.
+        if distance > distances[&state] || settled_states.contains(&state) {
+            continue;
+        }
+
+        settled_states.insert(state.clone());
+
+        if is_goal(&state) {
+            cost = Some(distance);
+            goals.push(state);
+            continue;
+        }
+
+        for (next_state, step_cost) in successors(&state) {
+            let next_distance = distance + step_cost;
+
+            match distances.get(&next_state) {
+                Some(&optimal_distance) if next_distance > optimal_distance => {}
+                Some(&optimal_distance) if next_distance == optimal_distance => {
+                    predecessors
+                        .entry(next_state)
+                        .or_default()
+                        .push(state.clone());
+                }
+                _ => {
+                    distances.insert(next_state.clone(), next_distance);
+                    predecessors.insert(next_state.clone(), vec![state.clone()]);
+
+                    visit_next.push(QueuedState {
+                        distance: next_distance,
+                        priority: Reverse(next_distance + heuristic(&next_state)),
+                        state: next_state,
+                    });
+                }
+            }
+        }
+    }
+
+    distances.retain(|state, _| settled_states.contains(state));
+    predecessors.retain(|state, _| settled_states.contains(state));
+
+    ShortestPaths {
+        cost,
+        distances,
+        goals,
+        predecessors,
+        start,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::IVec2;
+
+    use super::*;
+    use crate::grid::Grid;
+
+    /// A weighted maze where entering a cell costs its digit, and `#` cells are walls.
+    const MAZE: &str = "\
+        1121#\n\
+        1#91#\n\
+        13111\n\
+        #1#21\n\
+        11811";
+
+    fn maze() -> Grid<char> {
+        MAZE.lines().map(str::chars).collect()
+    }
+
+    fn maze_successors(maze: &Grid<char>, position: IVec2) -> Vec<(IVec2, u64)> {
+        maze.neighbors(position)
+            .filter_map(|neighbor| {
+                let cost = maze[neighbor].to_digit(10)?;
+
+                Some((neighbor, u64::from(cost)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn astar_matches_dijkstra() {
+        let maze = maze();
+
+        for goal in maze.positions().filter(|&position| maze[position] != '#') {
+            let dijkstra_paths = dijkstra(
+                IVec2::ZERO,
+                |&position| maze_successors(&maze, position),
+                |&position| position == goal,
+            );
+
+            // Every step costs at least 1, so the Manhattan distance never overestimates.
+            let astar_paths = astar(
+                IVec2::ZERO,
+                |&position| maze_successors(&maze, position),
+                |&position| u64::from((goal - position).abs().element_sum() as u32),
+                |&position| position == goal,
+            );
+
+            assert!(dijkstra_paths.cost().is_some(), "{goal} is reachable");
+            assert_eq!(astar_paths.cost(), dijkstra_paths.cost(), "Goal {goal}");
+            assert_eq!(
+                astar_paths.path().map(|path| path.len()),
+                dijkstra_paths.path().map(|path| path.len()),
+                "Goal {goal}"
+            );
+        }
+    }
+
+    #[test]
+    fn optimal_states_include_every_optimal_path() {
+        // Two paths from `a` to `d` cost 2, via `b` or `c`, while the path via `e` costs 6.
+        let edges = [
+            ('a', 'b', 1),
+            ('a', 'c', 1),
+            ('a', 'e', 1),
+            ('b', 'd', 1),
+            ('c', 'd', 1),
+            ('e', 'd', 5),
+        ];
+
+        let shortest_paths = dijkstra(
+            'a',
+            |&state| {
+                edges
+                    .iter()
+                    .filter(move |&&(from, ..)| from == state)
+                    .map(|&(_, to, cost)| (to, cost))
+            },
+            |&state| state == 'd',
+        );
+
+        assert_eq!(shortest_paths.cost(), Some(2));
+        assert_eq!(shortest_paths.goals(), ['d']);
+        assert_eq!(
+            shortest_paths.optimal_states(),
+            HashSet::from(['a', 'b', 'c', 'd'])
+        );
+
+        let mut predecessors = shortest_paths.predecessors(&'d').to_vec();
+        predecessors.sort_unstable();
+        assert_eq!(predecessors, ['b', 'c']);
+    }
+}
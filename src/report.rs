@@ -0,0 +1,128 @@
+use std::fmt::{self, Write as _};
+
+/// How diagnostic tables are written out.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Format {
+    Csv,
+    #[default]
+    Table,
+}
+
+/// A table of diagnostic output, which can be written either for reading in the terminal or as
+/// CSV for further processing.
+pub struct Table {
+    footer: Option<Vec<String>>,
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    title: String,
+}
+
+impl Table {
+    pub fn new(
+        title: impl Into<String>,
+        headers: impl IntoIterator<Item = impl fmt::Display>,
+    ) -> Self {
+        Self {
+            footer: None,
+            headers: headers
+                .into_iter()
+                .map(|header| header.to_string())
+                .collect(),
+            rows: Vec::new(),
+            title: title.into(),
+        }
+    }
+
+    pub fn push_row(&mut self, row: impl IntoIterator<Item = impl fmt::Display>) {
+        self.rows
+            .push(row.into_iter().map(|cell| cell.to_string()).collect());
+    }
+
+    /// Sets a final row, e.g. of totals, which is only shown in [`Format::Table`] output.
+    pub fn set_footer(&mut self, footer: impl IntoIterator<Item = impl fmt::Display>) {
+        self.footer = Some(footer.into_iter().map(|cell| cell.to_string()).collect());
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Csv => self.render_csv(),
+            Format::Table => self.render_table(),
+        }
+    }
+
+    fn render_csv(&self) -> String {
+        fn escape(cell: &str) -> String {
+            if cell.contains([',', '"', '\n']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_string()
+            }
+        }
+
+        let mut csv = String::new();
+
+        for row in [&self.headers].into_iter().chain(&self.rows) {
+            let row: Vec<String> = row.iter().map(|cell| escape(cell)).collect();
+
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    fn render_table(&self) -> String {
+        let all_rows = || {
+            [&self.headers]
+                .into_iter()
+                .chain(&self.rows)
+                .chain(&self.footer)
+        };
+
+        let column_count = all_rows().map(Vec::len).max().unwrap_or_default();
+        let mut column_widths = vec![0; column_count];
+
+        for row in all_rows() {
+            for (column_width, cell) in column_widths.iter_mut().zip(row) {
+                *column_width = (*column_width).max(cell.chars().count());
+            }
+        }
+
+        let separator: String = column_widths
+            .iter()
+            .map(|&column_width| "─".repeat(column_width + 2))
+            .collect::<Vec<_>>()
+            .join("┼");
+
+        let mut table = String::new();
+
+        let write_row = |table: &mut String, row: &[String]| {
+            let cells: Vec<String> = column_widths
+                .iter()
+                .enumerate()
+                .map(|(index, &column_width)| {
+                    let cell = row.get(index).map(String::as_str).unwrap_or_default();
+                    format!(" {cell:>column_width$} ")
+                })
+                .collect();
+
+            let _ = writeln!(table, "{}", cells.join("│"));
+        };
+
+        let _ = writeln!(table, "{}", self.title);
+
+        write_row(&mut table, &self.headers);
+        let _ = writeln!(table, "{separator}");
+
+        for row in &self.rows {
+            write_row(&mut table, row);
+        }
+
+        if let Some(footer) = &self.footer {
+            let _ = writeln!(table, "{separator}");
+            write_row(&mut table, footer);
+        }
+
+        table
+    }
+}